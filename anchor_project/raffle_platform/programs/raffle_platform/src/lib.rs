@@ -1,32 +1,587 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::program::MAX_RETURN_DATA;
+use anchor_lang::solana_program::system_instruction::MAX_PERMITTED_DATA_LENGTH;
+use anchor_spl::associated_token::{get_associated_token_address, AssociatedToken};
+use anchor_spl::metadata::{
+    self,
+    mpl_token_metadata::{
+        types::{Creator, DataV2},
+        MAX_URI_LENGTH,
+    },
+    Metadata,
+};
+use anchor_spl::token::{self, spl_token::instruction::AuthorityType, Mint, Token, TokenAccount};
 
 declare_id!("9Vu2g7S8oxYbk3JmHzjQXdoHguwEwPgVDq6KxAKAGWiW");
 
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Basis points representing 100%
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Share of the pot paid to the raffle creator (10%)
+pub const CREATOR_FEE_BPS: u16 = 1_000;
+
+/// Default minimum raffle duration, covering realistic confirmation times
+pub const DEFAULT_MIN_DURATION: i64 = 120;
+
+/// Seconds after the draw during which the winner can confirm prize delivery
+pub const BOND_DELIVERY_WINDOW: i64 = 7 * 24 * 60 * 60;
+
+/// Seconds after the draw during which the admin can claw back escrowed fees
+pub const CLAWBACK_WINDOW: i64 = 14 * 24 * 60 * 60;
+
+/// Number of entries a newly created raffle account can store
+pub const MAX_ENTRIES: usize = 20;
+
+/// Bytes each entry takes in a raffle account (buyer plus cumulative revenue)
+pub const ENTRY_SPACE: usize = 32 + 8;
+
+/// Schema version leading every `dump_state` snapshot
+pub const DUMP_STATE_VERSION: u8 = 1;
+
+/// Version of the winner selection algorithm new raffles are drawn with
+pub const DRAW_ALGO_VERSION: u8 = 1;
+
+/// Maximum number of payment mints the platform can allow
+pub const MAX_ALLOWED_MINTS: usize = 10;
+
+/// Number of recent raffles each `RaffleIndex` keeps
+pub const RAFFLE_INDEX_CAPACITY: usize = 32;
+
+/// Maximum number of winners a single raffle can draw
+pub const MAX_WINNERS: u8 = 10;
+
+/// Maximum number of separate prizes a raffle can offer
+pub const MAX_PRIZES: usize = 5;
+
+/// Maximum buyers returned per `get_buyers` page (keeps return data under 1024 bytes)
+pub const MAX_BUYERS_PAGE: u32 = 30;
+
+/// Maximum buyers listed by `entry_distribution` (keeps return data under 1024 bytes)
+pub const MAX_TOP_BUYERS: u8 = 10;
+
+/// Seconds a late purchase adds to `end_time` in an anti-snipe raffle
+pub const ANTI_SNIPE_EXTENSION: i64 = 5 * 60;
+
+/// Maximum number of times anti-snipe purchases can extend a raffle
+pub const MAX_END_TIME_EXTENSIONS: u8 = 12;
+
+/// Symbol of the Metaplex metadata of ticket NFTs
+pub const TICKET_NFT_SYMBOL: &str = "TICKET";
+
+/// `my_role` bit set when the queried key created the raffle
+pub const ROLE_CREATOR: u8 = 1 << 0;
+
+/// `my_role` bit set when the queried key won the raffle
+pub const ROLE_WINNER: u8 = 1 << 1;
+
+/// `my_role` bit set when the queried key holds at least one entry
+pub const ROLE_PARTICIPANT: u8 = 1 << 2;
+
 #[program]
 pub mod raffle_platform {
     use super::*;
 
+    /// Initializes the platform-wide configuration
+    ///
+    /// The signer becomes the platform admin. The minimum raffle duration
+    /// starts at `DEFAULT_MIN_DURATION` and can be changed with `update_config`.
+    /// The config is a singleton PDA: initializing it again fails, and the
+    /// program offers no way to close it.
+    ///
+    /// # Arguments
+    /// * `min_create_interval` - Minimum seconds between raffle creations by one creator
+    /// * `max_active_raffles` - Maximum concurrent active raffles per creator (0 = unlimited)
+    /// * `max_tickets_limit` - Upper bound for a raffle's `max_tickets`
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        min_create_interval: i64,
+        max_active_raffles: u32,
+        max_tickets_limit: u32,
+    ) -> Result<()> {
+        require!(min_create_interval >= 0, ErrorCode::InvalidConfig);
+        require!(
+            max_tickets_limit > 0 && max_tickets_limit < u32::MAX,
+            ErrorCode::InvalidConfig
+        );
+
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.min_create_interval = min_create_interval;
+        config.max_active_raffles = max_active_raffles;
+        config.max_tickets_limit = max_tickets_limit;
+        config.bump = ctx.bumps.config;
+        config.min_duration = DEFAULT_MIN_DURATION;
+        config.allowed_mints = Vec::new();
+
+        msg!(
+            "Platform config initialized! Admin: {}, Interval: {}s, Max Active: {}",
+            config.admin,
+            min_create_interval,
+            max_active_raffles
+        );
+
+        Ok(())
+    }
+
+    /// Updates the per-creator rate limits and raffle bounds (admin only)
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        min_create_interval: i64,
+        max_active_raffles: u32,
+        max_tickets_limit: u32,
+        min_duration: i64,
+    ) -> Result<()> {
+        require!(min_create_interval >= 0, ErrorCode::InvalidConfig);
+        require!(min_duration >= 0, ErrorCode::InvalidConfig);
+        require!(
+            max_tickets_limit > 0 && max_tickets_limit < u32::MAX,
+            ErrorCode::InvalidConfig
+        );
+
+        let config = &mut ctx.accounts.config;
+        config.min_create_interval = min_create_interval;
+        config.max_active_raffles = max_active_raffles;
+        config.max_tickets_limit = max_tickets_limit;
+        config.min_duration = min_duration;
+
+        msg!(
+            "Platform config updated! Interval: {}s, Max Active: {}",
+            min_create_interval,
+            max_active_raffles
+        );
+
+        Ok(())
+    }
+
+    /// Replaces the SPL mints raffles may be paid in (admin only)
+    ///
+    /// Raffles are paid in SOL until token payments exist, which will only
+    /// accept these mints.
+    pub fn set_allowed_mints(ctx: Context<UpdateConfig>, mints: Vec<Pubkey>) -> Result<()> {
+        require!(mints.len() <= MAX_ALLOWED_MINTS, ErrorCode::InvalidConfig);
+
+        let config = &mut ctx.accounts.config;
+        config.allowed_mints = mints;
+
+        msg!("Allowed payment mints updated: {}", config.allowed_mints.len());
+
+        Ok(())
+    }
+
+    /// Hands platform administration to `new_admin` (current admin only)
+    pub fn update_admin(ctx: Context<UpdateConfig>, new_admin: Pubkey) -> Result<()> {
+        require!(new_admin != Pubkey::default(), ErrorCode::InvalidConfig);
+
+        let config = &mut ctx.accounts.config;
+        let previous_admin = config.admin;
+        config.admin = new_admin;
+
+        msg!("Platform admin rotated from {} to {}", previous_admin, new_admin);
+
+        Ok(())
+    }
+
+    /// Marks a raffle as verified by the platform, or clears the mark (admin only)
+    pub fn set_verified(ctx: Context<SetVerified>, verified: bool) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        raffle.verified = verified;
+
+        emit!(RaffleVerified {
+            raffle: raffle.key(),
+            verified,
+        });
+
+        msg!("Raffle {} verified: {}", raffle.raffle_id, verified);
+
+        Ok(())
+    }
+
+    /// Freezes a raffle reported for trust and safety review (admin only)
+    ///
+    /// Blocks purchases and draws until `admin_unfreeze`. Unlike the soft cap
+    /// pause, the creator cannot lift it.
+    pub fn admin_freeze(ctx: Context<AdminFreeze>) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        raffle.frozen = true;
+
+        emit!(RaffleAdminFrozen {
+            raffle: raffle.key(),
+            admin: ctx.accounts.admin.key(),
+        });
+
+        msg!("Raffle {} frozen by the platform admin", raffle.raffle_id);
+
+        Ok(())
+    }
+
+    /// Lifts an `admin_freeze` (admin only)
+    pub fn admin_unfreeze(ctx: Context<AdminFreeze>) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        raffle.frozen = false;
+
+        emit!(RaffleAdminUnfrozen {
+            raffle: raffle.key(),
+            admin: ctx.accounts.admin.key(),
+        });
+
+        msg!("Raffle {} unfrozen by the platform admin", raffle.raffle_id);
+
+        Ok(())
+    }
+
+    /// Moves a frozen raffle's escrowed creator fee into a buyer refund pool
+    /// (admin only)
+    ///
+    /// For raffles frozen for fraud after the draw, within `CLAWBACK_WINDOW`
+    /// of it. Only fees still held by the raffle (`escrow_fees`) can be
+    /// reversed; the platform takes no fee of its own. Buyers then claim
+    /// their share with `claim_clawback_refund`.
+    pub fn admin_clawback(ctx: Context<AdminFreeze>) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let clock = Clock::get()?;
+
+        // Validation: Only drawn raffles under an admin freeze
+        require!(
+            raffle.state == RaffleState::Completed,
+            ErrorCode::RaffleNotCompleted
+        );
+        require!(raffle.frozen, ErrorCode::RaffleNotFrozen);
+
+        // Validation: Clawbacks are only accepted inside the dispute window
+        let deadline = raffle
+            .drawn_at
+            .checked_add(CLAWBACK_WINDOW)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            clock.unix_timestamp <= deadline,
+            ErrorCode::ClawbackWindowClosed
+        );
+
+        let creator_fee = raffle.accrued_creator_fee;
+        require!(creator_fee > 0, ErrorCode::NothingToClawBack);
+
+        raffle.accrued_creator_fee = 0;
+        raffle.clawback_pool = raffle
+            .clawback_pool
+            .checked_add(creator_fee)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(FeesClawedBack {
+            raffle: raffle.key(),
+            admin: ctx.accounts.admin.key(),
+            creator_fee,
+            refund_pool: raffle.clawback_pool,
+        });
+
+        msg!(
+            "Clawed back {} lamports of creator fees from raffle {}",
+            creator_fee,
+            raffle.raffle_id
+        );
+
+        Ok(())
+    }
+
+    /// Creates the `RaffleIndex` recording raffles entering `state` (admin only)
+    ///
+    /// Instructions moving a raffle into an indexed state (creation for
+    /// `Active`, draws for `Completed`, `mark_failed` for `Failed`) accept
+    /// the index as an optional account and append the raffle to it.
+    pub fn initialize_raffle_index(
+        ctx: Context<InitializeRaffleIndex>,
+        state: RaffleState,
+    ) -> Result<()> {
+        let index = &mut ctx.accounts.raffle_index;
+        index.state = state;
+        index.raffles = Vec::new();
+        index.cursor = 0;
+        index.bump = ctx.bumps.raffle_index;
+
+        msg!("Raffle index initialized for state {}", index.state.clone() as u8);
+
+        Ok(())
+    }
+
+    /// Creates the platform's TVL tracker (admin only, once)
+    ///
+    /// Purchases add their payment to it, and draws, refunds and
+    /// cancellations remove the revenue they settle, so it holds the ticket
+    /// revenue of raffles not yet settled.
+    pub fn initialize_tvl_tracker(ctx: Context<InitializeTvlTracker>) -> Result<()> {
+        let tvl_tracker = &mut ctx.accounts.tvl_tracker;
+        tvl_tracker.total_locked = 0;
+        tvl_tracker.bump = ctx.bumps.tvl_tracker;
+
+        msg!("TVL tracker initialized");
+
+        Ok(())
+    }
+
     /// Creates a new raffle with specified parameters
     ///
     /// # Arguments
     /// * `raffle_id` - Unique identifier for the raffle (managed by creator)
-    /// * `ticket_price` - Price per ticket in lamports
+    /// * `ticket_price` - Price per ticket in lamports (the start price with `end_price`)
     /// * `max_tickets` - Maximum number of tickets available
     /// * `end_time` - Unix timestamp when raffle ends
+    /// * `options` - Optional raffle features (selection mode, ...)
+    ///
+    /// With `options.idempotency_key`, `raffle_id` must be derived from the
+    /// key, so a retried creation targets the same account. A retry that
+    /// finds the raffle already created with that key succeeds without
+    /// changes.
     pub fn create_raffle(
         ctx: Context<CreateRaffle>,
         raffle_id: u64,
         ticket_price: u64,
         max_tickets: u32,
         end_time: i64,
+        options: RaffleOptions,
     ) -> Result<()> {
+        // Validation: Keyed creations use the id derived from the key
+        if let Some(key) = options.idempotency_key {
+            require!(
+                raffle_id == idempotent_raffle_id(&key),
+                ErrorCode::InvalidIdempotencyKey
+            );
+        }
+
+        // Idempotent retry: the raffle was already created with this key
+        let existing = &ctx.accounts.raffle;
+        if existing.creator != Pubkey::default() {
+            require!(
+                options.idempotency_key.is_some()
+                    && existing.idempotency_key == options.idempotency_key,
+                ErrorCode::RaffleAlreadyExists
+            );
+
+            msg!("Raffle {} already created, retry ignored", raffle_id);
+            return Ok(());
+        }
+
         // Validation
         require!(ticket_price > 0, ErrorCode::InvalidTicketPrice);
         require!(max_tickets > 0, ErrorCode::InvalidMaxTickets);
+        require!(
+            max_tickets <= ctx.accounts.config.max_tickets_limit,
+            ErrorCode::MaxTicketsTooLarge
+        );
+        require!(
+            options.min_tickets <= max_tickets && options.min_unique_buyers <= max_tickets,
+            ErrorCode::InvalidMinTickets
+        );
+
+        // Validation: Charity share needs a recipient and must fit in the pot
+        if options.charity_bps > 0 {
+            require!(options.charity.is_some(), ErrorCode::InvalidCharity);
+        }
+        require_splits_within_total(&[
+            options.charity_bps,
+            options.consolation_bps,
+            options.parent_share_bps,
+        ])?;
+
+        // Validation: Consolation comes out of the pot, which prize raffles don't split
+        if options.consolation_bps > 0 {
+            require!(options.prizes.is_empty(), ErrorCode::InvalidSplits);
+        }
+
+        // Validation: Winner count must be drawable from the ticket supply
+        require!(
+            options.winners > 0
+                && options.winners <= MAX_WINNERS
+                && options.winners as u32 <= max_tickets,
+            ErrorCode::InvalidWinnerCount
+        );
+
+        // Validation: Only a single pot winner can claim an escrowed prize
+        if options.escrow_prize {
+            require!(
+                options.winners == 1 && options.prizes.is_empty(),
+                ErrorCode::InvalidWinnerCount
+            );
+        }
+
+        // Validation: Vesting releases an escrowed prize in evenly spaced tranches
+        if options.vesting_tranches > 0 {
+            require!(
+                options.escrow_prize && options.vesting_interval > 0,
+                ErrorCode::InvalidVesting
+            );
+        }
+
+        // Validation: A child raffle feeds one winner into a parent of the
+        // same creator that is still selling and ends after it. Parents are
+        // drawn per ticket, as advanced entries carry no revenue.
+        if let Some(parent_key) = options.parent {
+            let parent = ctx
+                .accounts
+                .parent_raffle
+                .as_mut()
+                .ok_or(ErrorCode::InvalidParent)?;
+            require!(
+                parent.key() == parent_key
+                    && parent.creator == ctx.accounts.creator.key()
+                    && parent.parent.is_none()
+                    && parent.selection_mode == SelectionMode::PerTicket
+                    && (parent.state == RaffleState::Active
+                        || parent.state == RaffleState::Pending)
+                    && end_time <= parent.end_time
+                    && options.winners == 1
+                    && options.prizes.is_empty(),
+                ErrorCode::InvalidParent
+            );
+
+            // Count the child on its parent, which cannot close before it
+            parent.child_count = parent
+                .child_count
+                .checked_add(1)
+                .ok_or(ErrorCode::MathOverflow)?;
+        } else {
+            require!(options.parent_share_bps == 0, ErrorCode::InvalidParent);
+        }
+
+        // Validation: Commit-reveal needs a commitment, a beacon needs its
+        // round and publisher, VRF has no oracle yet
+        match options.randomness_source {
+            RandomnessSource::CommitReveal => {
+                require!(options.commitment.is_some(), ErrorCode::MissingCommitment)
+            }
+            RandomnessSource::Beacon => require!(
+                options.beacon_round > 0 && options.beacon_signer.is_some(),
+                ErrorCode::MissingBeacon
+            ),
+            RandomnessSource::Vrf => return err!(ErrorCode::UnsupportedRandomnessSource),
+            RandomnessSource::SlotBased | RandomnessSource::SlotHashes => {}
+        }
+
+        // Validation: A snapshot needs an attestor, in the past
+        if options.snapshot_slot > 0 || options.snapshot_attestor.is_some() {
+            require!(
+                options.snapshot_slot > 0 && options.snapshot_attestor.is_some(),
+                ErrorCode::InvalidSnapshot
+            );
+            require!(
+                options.snapshot_slot <= Clock::get()?.slot,
+                ErrorCode::InvalidSnapshot
+            );
+        }
+
+        // Validation: Prizes fit the account and carry a short label
+        require!(
+            options.prizes.len() <= MAX_PRIZES
+                && options.prizes.len() as u32 <= max_tickets
+                && options
+                    .prizes
+                    .iter()
+                    .all(|prize| !prize.label.is_empty() && prize.label.len() <= 32),
+            ErrorCode::InvalidPrizes
+        );
+
+        // Validation: The keeper reward must respect the creator's tip cap
+        if options.max_keeper_tip > 0 {
+            require!(
+                options.keeper_reward <= options.max_keeper_tip,
+                ErrorCode::InvalidKeeperTip
+            );
+        }
+
+        // Validation: Holder discount needs a mint and must keep tickets paid
+        if options.discount_bps > 0 {
+            require!(options.discount_mint.is_some(), ErrorCode::InvalidDiscount);
+        }
+        require!(
+            (options.discount_bps as u64) < BPS_DENOMINATOR,
+            ErrorCode::InvalidDiscount
+        );
+
+        // Validation: A draw-time gate needs a mint and a single pot draw
+        if options.require_gate_at_draw {
+            require!(
+                options.gate_mint.is_some() && options.prizes.is_empty(),
+                ErrorCode::InvalidGate
+            );
+        }
+
+        // Validation: Fallback draws trust the drawer to pass the winners'
+        // accounts, so only a set draw authority may run them
+        if options.allow_fallback {
+            require!(options.draw_authority.is_some(), ErrorCode::InvalidFallback);
+        }
+
+        // Validation: A price curve must actually move the price
+        require!(
+            options.end_price != ticket_price,
+            ErrorCode::InvalidPriceCurve
+        );
 
         let clock = Clock::get()?;
         require!(end_time > clock.unix_timestamp, ErrorCode::InvalidEndTime);
 
+        // Validation: A fixed prize tops up the pot, which prize raffles don't split
+        if options.fixed_prize > 0 {
+            require!(options.prizes.is_empty(), ErrorCode::InvalidPrizes);
+        }
+
+        // Validation: A scheduled launch opens before the raffle ends
+        require!(options.sale_start < end_time, ErrorCode::InvalidSaleStart);
+
+        // Validation: The anti-snipe window cannot be negative
+        require!(options.anti_snipe_window >= 0, ErrorCode::InvalidAntiSnipeWindow);
+
+        // Validation: The cooling-off window cannot be negative
+        require!(options.cooling_off >= 0, ErrorCode::InvalidCoolingOff);
+        let pending = options.sale_start > clock.unix_timestamp;
+
+        // Validation: Leave buyers a realistic window to enter
+        let duration = end_time
+            .checked_sub(clock.unix_timestamp)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            duration >= ctx.accounts.config.min_duration,
+            ErrorCode::DurationTooShort
+        );
+
+        // Rate limiting: enforce per-creator cooldown and concurrency cap
+        let config = &ctx.accounts.config;
+        let creator_state = &mut ctx.accounts.creator_state;
+
+        if creator_state.last_created_at > 0 {
+            let elapsed = clock
+                .unix_timestamp
+                .checked_sub(creator_state.last_created_at)
+                .ok_or(ErrorCode::MathOverflow)?;
+            require!(
+                elapsed >= config.min_create_interval,
+                ErrorCode::CreateCooldownActive
+            );
+        }
+
+        if config.max_active_raffles > 0 {
+            require!(
+                creator_state.active_raffle_count < config.max_active_raffles,
+                ErrorCode::TooManyActiveRaffles
+            );
+        }
+
+        creator_state.creator = ctx.accounts.creator.key();
+        creator_state.last_created_at = clock.unix_timestamp;
+        creator_state.active_raffle_count = creator_state
+            .active_raffle_count
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        creator_state.created_count = creator_state
+            .created_count
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        creator_state.bump = ctx.bumps.creator_state;
+
         // Initialize raffle account
         let raffle = &mut ctx.accounts.raffle;
         raffle.creator = ctx.accounts.creator.key();
@@ -36,9 +591,151 @@ pub mod raffle_platform {
         raffle.total_tickets_sold = 0;
         raffle.ticket_buyers = Vec::new(); // Initialize empty vector for ticket buyers
         raffle.winner = None;
-        raffle.state = RaffleState::Active;
+        raffle.state = if pending {
+            RaffleState::Pending
+        } else {
+            RaffleState::Active
+        };
         raffle.bump = ctx.bumps.raffle;
         raffle.raffle_id = raffle_id;
+        raffle.selection_mode = options.selection_mode;
+        raffle.total_revenue = 0;
+        raffle.entry_cumulative = Vec::new();
+        raffle.min_tickets = options.min_tickets;
+        raffle.tickets_refunded = 0;
+        raffle.purchase_sequence = 0;
+        raffle.charity = options.charity;
+        raffle.charity_bps = options.charity_bps;
+        raffle.discount_mint = options.discount_mint;
+        raffle.discount_bps = options.discount_bps;
+        raffle.keeper_reward = options.keeper_reward;
+        raffle.ended_at_slot = 0;
+        raffle.tokenized_entries = 0;
+        raffle.winners_count = options.winners;
+        raffle.winners = Vec::new();
+        raffle.soft_cap = options.soft_cap;
+        raffle.soft_cap_cleared = false;
+        raffle.randomness_source = options.randomness_source;
+        raffle.commitment = options.commitment;
+        raffle.revealed_secret = None;
+        raffle.draw_attempts = 0;
+        raffle.max_total_revenue = options.max_total_revenue;
+        raffle.end_at_revenue_cap = options.end_at_revenue_cap;
+        raffle.draw_seed = 0;
+        raffle.draw_slot = 0;
+        raffle.winning_indices = Vec::new();
+        raffle.one_per_wallet = options.one_per_wallet;
+        raffle.min_pot = options.min_pot;
+        raffle.escrow_fees = options.escrow_fees;
+        raffle.accrued_creator_fee = 0;
+        raffle.snapshot_slot = options.snapshot_slot;
+        raffle.snapshot_attestor = options.snapshot_attestor;
+        raffle.ticket_number_offset = options.ticket_number_offset;
+        raffle.prizes = options.prizes;
+        raffle.drawn_count = 0;
+        raffle.allow_multiple_wins = options.allow_multiple_wins;
+        raffle.prize_entry_indices = Vec::new();
+        raffle.prize_winners = Vec::new();
+        raffle.bond = options.bond;
+        raffle.bond_released = false;
+        raffle.drawn_at = 0;
+        raffle.idempotency_key = options.idempotency_key;
+        raffle.batch_winner_prize = 0;
+        raffle.verified = false;
+        raffle.end_price = options.end_price;
+        raffle.price_start_time = clock.unix_timestamp;
+        raffle.exclude_last_buyer = options.exclude_last_buyer;
+        raffle.allow_early_draw = options.allow_early_draw;
+        raffle.max_keeper_tip = options.max_keeper_tip;
+        raffle.escrow_prize = options.escrow_prize;
+        raffle.prize_owed = 0;
+        raffle.claimed_so_far = 0;
+        raffle.termination_reason = None;
+        raffle.buyers_frozen = false;
+        raffle.entry_capacity = MAX_ENTRIES as u32;
+        raffle.consolation_bps = options.consolation_bps;
+        raffle.consolation_pool = 0;
+        raffle.consolation_entries = 0;
+        raffle.consolation_entries_claimed = 0;
+        // Prizes are delivered off-chain (see the creator bond), so there is
+        // no escrowed prize NFT whose Metaplex collection could be checked
+        // against this; clients must not present it as verified
+        raffle.prize_collection = options.prize_collection;
+        raffle.sale_start = options.sale_start;
+        raffle.interest_count = 0;
+        raffle.fixed_prize = options.fixed_prize;
+        raffle.fixed_prize_settled = false;
+        raffle.shrink_winners = options.shrink_winners;
+        raffle.beacon_round = options.beacon_round;
+        raffle.beacon_signer = options.beacon_signer;
+        raffle.beacon_value = None;
+        raffle.vesting_tranches = options.vesting_tranches;
+        raffle.vesting_interval = options.vesting_interval;
+        // Creator fees go to the creator's treasury when asked to
+        raffle.fee_recipient = if options.use_treasury {
+            ctx.accounts
+                .treasury
+                .as_ref()
+                .ok_or(ErrorCode::TreasuryRequired)?
+                .key()
+        } else {
+            ctx.accounts.creator.key()
+        };
+        raffle.parent = options.parent;
+        raffle.parent_share_bps = options.parent_share_bps;
+        raffle.winner_advanced = false;
+        raffle.auto_draw_on_sellout = options.auto_draw_on_sellout;
+        raffle.frozen = false;
+        raffle.winner_prize = 0;
+        raffle.allow_fallback = options.allow_fallback;
+        raffle.draw_fallbacks = Vec::new();
+        raffle.anti_snipe_window = options.anti_snipe_window;
+        raffle.end_time_extensions = 0;
+        raffle.gate_mint = options.gate_mint;
+        raffle.claim_code = options.claim_code;
+        raffle.claim_code_revealed = false;
+        raffle.min_unique_buyers = options.min_unique_buyers;
+        raffle.draw_authority = options.draw_authority;
+        raffle.algo_version = DRAW_ALGO_VERSION;
+        raffle.clawback_pool = 0;
+        raffle.clawback_entries_claimed = 0;
+        raffle.child_count = 0;
+        raffle.require_gate_at_draw = options.require_gate_at_draw;
+        raffle.cooling_off_end = clock
+            .unix_timestamp
+            .checked_add(options.cooling_off)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        // Hold the creator's delivery bond and fixed prize in the raffle, in
+        // this instruction so the raffle never exists without them
+        let deposit = options
+            .bond
+            .checked_add(options.fixed_prize)
+            .ok_or(ErrorCode::MathOverflow)?;
+        if deposit > 0 {
+            // Validation: The creator can cover the whole deposit
+            require!(
+                ctx.accounts.creator.lamports() >= deposit,
+                ErrorCode::InsufficientDeposit
+            );
+
+            let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.creator.key(),
+                &raffle.key(),
+                deposit,
+            );
+
+            anchor_lang::solana_program::program::invoke(
+                &transfer_ix,
+                &[
+                    ctx.accounts.creator.to_account_info(),
+                    raffle.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+
+        record_in_index(&mut ctx.accounts.raffle_index, raffle.key(), raffle.state.clone())?;
 
         msg!(
             "Raffle created! ID: {}, Price: {}, Max Tickets: {}, Ends: {}",
@@ -54,28 +751,58 @@ pub mod raffle_platform {
     /// Purchases a ticket for an active raffle
     ///
     /// Transfers SOL from buyer to raffle PDA and creates a ticket account.
-    /// Automatically transitions raffle to Ended state if max tickets reached.
-    pub fn buy_ticket(ctx: Context<BuyTicket>) -> Result<()> {
+    /// Buyers holding the raffle's `discount_mint` may pass their token
+    /// account to pay the discounted price.
+    /// With `on_behalf_of`, the signer sponsors a gifted entry: the signer
+    /// pays the price and ticket rent, while the recipient is recorded as
+    /// the ticket's buyer and is the one who can win or claim a refund.
+    /// Automatically transitions raffle to Ended state if max tickets reached,
+    /// or pauses sales once revenue reaches the raffle's `soft_cap`.
+    pub fn buy_ticket(ctx: Context<BuyTicket>, on_behalf_of: Option<Pubkey>) -> Result<()> {
         let raffle = &mut ctx.accounts.raffle;
         let clock = Clock::get()?;
 
-        // Validation: Check raffle is active
+        // Validation: Check raffle is active, not sold out and not expired
+        raffle.require_purchasable(clock.unix_timestamp)?;
+
+        // Gifted entries are recorded for the recipient, paid by the signer
+        let payer_key = ctx.accounts.buyer.key();
+        let buyer_key = match on_behalf_of {
+            Some(recipient) => {
+                require!(recipient != Pubkey::default(), ErrorCode::InvalidRecipient);
+                recipient
+            }
+            None => payer_key,
+        };
+
+        // Apply the holder discount when a valid token account is provided
+        let price = ticket_price_for(
+            raffle,
+            &ctx.accounts.discount_token_account,
+            payer_key,
+            clock.unix_timestamp,
+        )?;
+
+        // Validation: Purchase must not push revenue past the cap
         require!(
-            raffle.state == RaffleState::Active,
-            ErrorCode::RaffleNotActive
+            raffle.tickets_within_revenue_cap(price) > 0,
+            ErrorCode::RevenueCapReached
         );
 
-        // Validation: Check tickets available
+        // Validation: Entrant must still be allowed another ticket
         require!(
-            raffle.total_tickets_sold < raffle.max_tickets,
-            ErrorCode::RaffleSoldOut
+            raffle.wallet_allowance(ctx.accounts.buyer_record.tickets_bought) > 0,
+            ErrorCode::AlreadyEntered
         );
 
+        // Validation: Snapshot-gated raffles need the attestor's signature
+        require_snapshot_attestation(raffle, &ctx.accounts.snapshot_attestor)?;
+
         // Transfer SOL from buyer to raffle PDA
         let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
-            &ctx.accounts.buyer.key(),
+            &payer_key,
             &raffle.key(),
-            raffle.ticket_price,
+            price,
         );
 
         anchor_lang::solana_program::program::invoke(
@@ -87,260 +814,5950 @@ pub mod raffle_platform {
             ],
         )?;
 
-        // Initialize ticket account
-        let ticket = &mut ctx.accounts.ticket;
-        ticket.raffle = raffle.key();
-        ticket.buyer = ctx.accounts.buyer.key();
-        ticket.ticket_number = raffle.total_tickets_sold;
-        ticket.purchase_time = clock.unix_timestamp;
-        ticket.bump = ctx.bumps.ticket;
+        // Count the payment toward the platform's TVL
+        ctx.accounts.tvl_tracker.lock(price)?;
 
-        // Add buyer to the ticket_buyers vector
-        raffle.ticket_buyers.push(ctx.accounts.buyer.key());
+        // Initialize ticket account
+        let ticket = record_entry(raffle, buyer_key, price, clock.unix_timestamp, ctx.bumps.ticket)?;
+        ctx.accounts.ticket.set_inner(ticket);
 
-        // Increment ticket count
-        raffle.total_tickets_sold = raffle
-            .total_tickets_sold
-            .checked_add(1)
-            .ok_or(ErrorCode::MathOverflow)?;
+        // Track the purchase on the entrant's buyer record
+        record_purchase(
+            &mut ctx.accounts.buyer_record,
+            raffle.key(),
+            buyer_key,
+            1,
+            price,
+            clock.unix_timestamp,
+            ctx.bumps.buyer_record,
+        )?;
 
         msg!(
             "Ticket #{} purchased by {} for raffle {}",
-            ticket.ticket_number,
-            ctx.accounts.buyer.key(),
+            ctx.accounts.ticket.ticket_number,
+            buyer_key,
             raffle.raffle_id
         );
+        if buyer_key != payer_key {
+            msg!("Ticket gifted by {}", payer_key);
+        }
 
-        // Check if raffle should end (max tickets reached)
-        if raffle.total_tickets_sold >= raffle.max_tickets {
-            raffle.state = RaffleState::Ended;
-            msg!("Raffle {} has ended (sold out)", raffle.raffle_id);
+        // Push the end back when the purchase lands in the anti-snipe window
+        if let Some(old_end_time) = raffle.extend_for_late_purchase(clock.unix_timestamp)? {
+            emit!(EndTimeExtended {
+                raffle: raffle.key(),
+                old_end_time,
+                new_end_time: raffle.end_time,
+                extensions: raffle.end_time_extensions,
+            });
         }
 
+        // Check if raffle should end (max tickets reached) or pause (soft cap)
+        raffle.settle_sales_state(clock.slot);
+
         Ok(())
     }
 
-    /// Draws a winner for an ended raffle and distributes prizes
+    /// Purchases a ticket through a relayer that pays fees and rent
     ///
-    /// Uses slot-based randomness to select a winning ticket.
-    /// Distributes 90% to winner and 10% to creator.
-    pub fn draw_winner(ctx: Context<DrawWinner>) -> Result<()> {
+    /// The buyer still signs and pays the ticket price. `expected_nonce`
+    /// must equal the buyer record's current nonce, so a signed relayed
+    /// purchase cannot be replayed once any purchase has been recorded.
+    pub fn buy_ticket_relayed(ctx: Context<BuyTicketRelayed>, expected_nonce: u64) -> Result<()> {
         let raffle = &mut ctx.accounts.raffle;
         let clock = Clock::get()?;
 
-        // Check if raffle should end based on time (if not already ended)
-        if raffle.state == RaffleState::Active && clock.unix_timestamp >= raffle.end_time {
-            raffle.state = RaffleState::Ended;
-            msg!("Raffle {} has ended (time expired)", raffle.raffle_id);
-        }
+        // Validation: Check raffle is active, not sold out and not expired
+        raffle.require_purchasable(clock.unix_timestamp)?;
 
-        // Validation: Check raffle is in Ended state
+        // Validation: Reject replays of an already used nonce
         require!(
-            raffle.state == RaffleState::Ended,
-            ErrorCode::RaffleNotEnded
+            ctx.accounts.buyer_record.nonce == expected_nonce,
+            ErrorCode::StaleNonce
         );
 
-        // Validation: Check tickets were sold
+        // Apply the holder discount when a valid token account is provided
+        let buyer_key = ctx.accounts.buyer.key();
+        let price = ticket_price_for(
+            raffle,
+            &ctx.accounts.discount_token_account,
+            buyer_key,
+            clock.unix_timestamp,
+        )?;
+
+        // Validation: Purchase must not push revenue past the cap
         require!(
-            raffle.total_tickets_sold > 0,
-            ErrorCode::NoTicketsSold
+            raffle.tickets_within_revenue_cap(price) > 0,
+            ErrorCode::RevenueCapReached
         );
 
-        // Use slot-based randomness to pick winner from stored ticket_buyers
-        let slot = clock.slot;
-        let winning_index = (slot % raffle.total_tickets_sold as u64) as usize;
-        let winner_pubkey = raffle.ticket_buyers[winning_index];
-
-        msg!("Drawing winner for raffle {}", raffle.raffle_id);
-        msg!("Winning ticket index: {}", winning_index);
-        msg!("Winner: {}", winner_pubkey);
-
-        // Find the winner account in remaining_accounts
-        let winner_account = ctx.remaining_accounts
-            .iter()
-            .find(|acc| acc.key() == winner_pubkey)
-            .ok_or(ErrorCode::InvalidWinningTicket)?;
-
-        // Get raffle's total balance
-        let raffle_balance = raffle.to_account_info().lamports();
-
-        // Calculate prize distribution: 90% to winner, 10% to creator
-        let winner_prize = raffle_balance
-            .checked_mul(90)
-            .ok_or(ErrorCode::MathOverflow)?
-            .checked_div(100)
-            .ok_or(ErrorCode::MathOverflow)?;
-
-        let creator_fee = raffle_balance
-            .checked_sub(winner_prize)
-            .ok_or(ErrorCode::MathOverflow)?;
+        // Validation: Entrant must still be allowed another ticket
+        require!(
+            raffle.wallet_allowance(ctx.accounts.buyer_record.tickets_bought) > 0,
+            ErrorCode::AlreadyEntered
+        );
 
-        msg!("Total balance: {} lamports", raffle_balance);
-        msg!("Winner prize: {} lamports (90%)", winner_prize);
-        msg!("Creator fee: {} lamports (10%)", creator_fee);
+        // Validation: Snapshot-gated raffles need the attestor's signature
+        require_snapshot_attestation(raffle, &ctx.accounts.snapshot_attestor)?;
 
-        // Transfer winner prize
-        **raffle.to_account_info().try_borrow_mut_lamports()? = raffle
-            .to_account_info()
-            .lamports()
-            .checked_sub(winner_prize)
-            .ok_or(ErrorCode::MathOverflow)?;
+        // Transfer SOL from buyer to raffle PDA
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &buyer_key,
+            &raffle.key(),
+            price,
+        );
 
-        **winner_account.try_borrow_mut_lamports()? = winner_account
-            .lamports()
-            .checked_add(winner_prize)
-            .ok_or(ErrorCode::MathOverflow)?;
+        anchor_lang::solana_program::program::invoke(
+            &transfer_ix,
+            &[
+                ctx.accounts.buyer.to_account_info(),
+                raffle.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
 
-        // Transfer creator fee
-        **raffle.to_account_info().try_borrow_mut_lamports()? = raffle
-            .to_account_info()
-            .lamports()
-            .checked_sub(creator_fee)
-            .ok_or(ErrorCode::MathOverflow)?;
+        // Count the payment toward the platform's TVL
+        ctx.accounts.tvl_tracker.lock(price)?;
 
-        **ctx.accounts.creator.to_account_info().try_borrow_mut_lamports()? = ctx
-            .accounts
-            .creator
-            .to_account_info()
-            .lamports()
-            .checked_add(creator_fee)
-            .ok_or(ErrorCode::MathOverflow)?;
+        // Initialize ticket account
+        let ticket = record_entry(raffle, buyer_key, price, clock.unix_timestamp, ctx.bumps.ticket)?;
+        ctx.accounts.ticket.set_inner(ticket);
 
-        // Update raffle state
-        raffle.winner = Some(winner_pubkey);
-        raffle.state = RaffleState::Completed;
+        // Track the purchase on the buyer record (advances the nonce)
+        record_purchase(
+            &mut ctx.accounts.buyer_record,
+            raffle.key(),
+            buyer_key,
+            1,
+            price,
+            clock.unix_timestamp,
+            ctx.bumps.buyer_record,
+        )?;
 
         msg!(
-            "Winner drawn! {} wins {} lamports",
-            winner_pubkey,
-            winner_prize
+            "Ticket #{} purchased by {} for raffle {} (relayed by {})",
+            ctx.accounts.ticket.ticket_number,
+            buyer_key,
+            raffle.raffle_id,
+            ctx.accounts.relayer.key()
         );
 
+        // Push the end back when the purchase lands in the anti-snipe window
+        if let Some(old_end_time) = raffle.extend_for_late_purchase(clock.unix_timestamp)? {
+            emit!(EndTimeExtended {
+                raffle: raffle.key(),
+                old_end_time,
+                new_end_time: raffle.end_time,
+                extensions: raffle.end_time_extensions,
+            });
+        }
+
+        // Check if raffle should end (max tickets reached) or pause (soft cap)
+        raffle.settle_sales_state(clock.slot);
+
         Ok(())
     }
-}
+
+    /// Purchases several tickets for an active raffle in one transaction
+    ///
+    /// Fills up to the remaining capacity: if fewer than `quantity` tickets
+    /// are left, only those are granted and only those are charged. The
+    /// ticket PDAs for the next ticket numbers must be passed in
+    /// `remaining_accounts`, in order.
+    pub fn buy_tickets<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BuyTickets<'info>>,
+        quantity: u32,
+    ) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let raffle_key = raffle.key();
+        let buyer_key = ctx.accounts.buyer.key();
+        let clock = Clock::get()?;
+
+        // Validation: At least one ticket must be requested
+        require!(quantity > 0, ErrorCode::InvalidQuantity);
+
+        // Validation: Check raffle is active, not sold out and not expired
+        raffle.require_purchasable(clock.unix_timestamp)?;
+
+        let price = ticket_price_for(
+            raffle,
+            &ctx.accounts.discount_token_account,
+            buyer_key,
+            clock.unix_timestamp,
+        )?;
+
+        // Partial fill: grant only what is left before the sold-out and revenue caps
+        let available = raffle
+            .max_tickets
+            .checked_sub(raffle.total_tickets_sold)
+            .ok_or(ErrorCode::MathOverflow)?;
+        // Validation: Entrant must still be allowed another ticket
+        let allowance = raffle.wallet_allowance(ctx.accounts.buyer_record.tickets_bought);
+        require!(allowance > 0, ErrorCode::AlreadyEntered);
+
+        // Validation: Snapshot-gated raffles need the attestor's signature
+        require_snapshot_attestation(raffle, &ctx.accounts.snapshot_attestor)?;
+
+        let granted = quantity
+            .min(available)
+            .min(allowance)
+            .min(raffle.tickets_within_revenue_cap(price));
+
+        // Validation: The revenue cap leaves room for at least one ticket
+        require!(granted > 0, ErrorCode::RevenueCapReached);
+
+        // Validation: One ticket account per granted ticket
+        require!(
+            ctx.remaining_accounts.len() >= granted as usize,
+            ErrorCode::InvalidTicketAccount
+        );
+
+        let total_paid = price
+            .checked_mul(granted as u64)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        // Transfer SOL for the granted tickets only
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &buyer_key,
+            &raffle_key,
+            total_paid,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &transfer_ix,
+            &[
+                ctx.accounts.buyer.to_account_info(),
+                raffle.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        // Count the payment toward the platform's TVL
+        ctx.accounts.tvl_tracker.lock(total_paid)?;
+
+        let space = 8 + Ticket::INIT_SPACE;
+        let rent = Rent::get()?.minimum_balance(space);
+
+        for ticket_info in ctx.remaining_accounts.iter().take(granted as usize) {
+            let entry_index = raffle.total_tickets_sold.to_le_bytes();
+            let (expected_ticket, bump) = Pubkey::find_program_address(
+                &[b"ticket", raffle_key.as_ref(), entry_index.as_ref()],
+                ctx.program_id,
+            );
+
+            // Validation: Account must be the PDA of the next ticket number
+            require_keys_eq!(
+                ticket_info.key(),
+                expected_ticket,
+                ErrorCode::InvalidTicketAccount
+            );
+
+            let signer_seeds: &[&[&[u8]]] =
+                &[&[b"ticket", raffle_key.as_ref(), entry_index.as_ref(), &[bump]]];
+
+            anchor_lang::system_program::create_account(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::CreateAccount {
+                        from: ctx.accounts.buyer.to_account_info(),
+                        to: ticket_info.clone(),
+                    },
+                    signer_seeds,
+                ),
+                rent,
+                space as u64,
+                ctx.program_id,
+            )?;
+
+            let ticket = record_entry(raffle, buyer_key, price, clock.unix_timestamp, bump)?;
+            ticket.try_serialize(&mut &mut ticket_info.try_borrow_mut_data()?[..])?;
+        }
+
+        // Track the purchase on the buyer record
+        record_purchase(
+            &mut ctx.accounts.buyer_record,
+            raffle_key,
+            buyer_key,
+            granted,
+            total_paid,
+            clock.unix_timestamp,
+            ctx.bumps.buyer_record,
+        )?;
+
+        emit!(TicketsPurchased {
+            raffle: raffle_key,
+            buyer: buyer_key,
+            requested: quantity,
+            granted,
+            total_paid,
+        });
+
+        msg!(
+            "{} of {} tickets purchased by {} for raffle {}",
+            granted,
+            quantity,
+            buyer_key,
+            raffle.raffle_id
+        );
+
+        // Push the end back when the purchase lands in the anti-snipe window
+        if let Some(old_end_time) = raffle.extend_for_late_purchase(clock.unix_timestamp)? {
+            emit!(EndTimeExtended {
+                raffle: raffle_key,
+                old_end_time,
+                new_end_time: raffle.end_time,
+                extensions: raffle.end_time_extensions,
+            });
+        }
+
+        // Check if raffle should end (max tickets reached) or pause (soft cap)
+        raffle.settle_sales_state(clock.slot);
+
+        Ok(())
+    }
+
+    /// Amends the charity split before any ticket is sold (creator only)
+    ///
+    /// The creator fee is fixed at `CREATOR_FEE_BPS`, so the charity share
+    /// is the only adjustable split. Once a ticket is sold the splits are
+    /// locked for good.
+    pub fn set_splits(
+        ctx: Context<SetSplits>,
+        charity: Option<Pubkey>,
+        charity_bps: u16,
+    ) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+
+        // Validation: Splits are locked once anyone has bought in
+        require!(
+            (raffle.state == RaffleState::Active || raffle.state == RaffleState::Pending)
+                && raffle.total_tickets_sold == 0,
+            ErrorCode::CannotModifyAfterSales
+        );
+
+        // Validation: Charity share needs a recipient and must fit in the pot
+        if charity_bps > 0 {
+            require!(charity.is_some(), ErrorCode::InvalidCharity);
+        }
+        require_splits_within_total(&[
+            charity_bps,
+            raffle.consolation_bps,
+            raffle.parent_share_bps,
+        ])?;
+
+        raffle.charity = charity;
+        raffle.charity_bps = charity_bps;
+
+        msg!("Raffle {} splits updated: charity {} bps", raffle.raffle_id, charity_bps);
+
+        Ok(())
+    }
+
+    /// Reassigns the account receiving the creator fee (creator only)
+    ///
+    /// Lets a mistyped or compromised recipient be replaced before the draw
+    /// pays the fee out.
+    pub fn set_fee_recipient(ctx: Context<SetFeeRecipient>, new_recipient: Pubkey) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+
+        // Validation: The fee is paid once the raffle completes
+        require!(raffle.state != RaffleState::Completed, ErrorCode::RaffleAlreadyCompleted);
+
+        let old_recipient = raffle.fee_recipient;
+        raffle.fee_recipient = new_recipient;
+
+        emit!(FeeRecipientChanged {
+            raffle: raffle.key(),
+            old_recipient,
+            new_recipient,
+        });
+
+        msg!("Raffle {} fee recipient set to {}", raffle.raffle_id, new_recipient);
+
+        Ok(())
+    }
+
+    /// Grows the raffle account to store `new_capacity` entries (creator only)
+    ///
+    /// Reallocates the account, with the creator paying the extra rent, so a
+    /// popular raffle can sell past its initial `entry_capacity`. A single
+    /// call can add at most 10 KiB, so large increases take several calls.
+    pub fn grow_raffle(ctx: Context<GrowRaffle>, new_capacity: u32) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let raffle_info = raffle.to_account_info();
+        let current_len = raffle_info.data_len();
+        let new_len = Raffle::space_for(new_capacity);
+
+        // Validation: Capacity only grows, within the maximum account size
+        require!(
+            new_capacity > raffle.entry_capacity,
+            ErrorCode::InvalidCapacity
+        );
+        require!(
+            new_len as u64 <= MAX_PERMITTED_DATA_LENGTH,
+            ErrorCode::InvalidCapacity
+        );
+
+        // Validation: One call grows the account by at most 10 KiB
+        require!(
+            new_len.saturating_sub(current_len) <= MAX_PERMITTED_DATA_INCREASE,
+            ErrorCode::InvalidCapacity
+        );
+
+        // The creator pays the rent of the added space, leaving the pot untouched
+        let rent = Rent::get()?;
+        let extra_rent = rent
+            .minimum_balance(new_len)
+            .saturating_sub(rent.minimum_balance(current_len));
+        if extra_rent > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.creator.to_account_info(),
+                        to: raffle_info.clone(),
+                    },
+                ),
+                extra_rent,
+            )?;
+        }
+        raffle_info.resize(new_len)?;
+
+        raffle.entry_capacity = new_capacity;
+
+        msg!("Raffle {} can now store {} entries", raffle.raffle_id, new_capacity);
+
+        Ok(())
+    }
+
+    /// Resumes or ends a raffle paused at its soft cap
+    ///
+    /// Only the creator can decide. Resuming clears the soft cap so sales
+    /// continue up to `max_tickets`; otherwise the raffle ends and can be
+    /// drawn.
+    pub fn confirm_continue(ctx: Context<ConfirmContinue>, resume: bool) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let clock = Clock::get()?;
+
+        // Validation: Only paused raffles await confirmation
+        require!(
+            raffle.state == RaffleState::PausedAtSoftCap,
+            ErrorCode::RaffleNotPaused
+        );
+
+        if resume {
+            raffle.state = RaffleState::Active;
+            raffle.soft_cap_cleared = true;
+            msg!("Raffle {} resumed past soft cap", raffle.raffle_id);
+        } else {
+            raffle.mark_ended(clock.slot);
+            msg!("Raffle {} has ended (soft cap reached)", raffle.raffle_id);
+        }
+
+        Ok(())
+    }
+
+    /// Registers the signer's interest in a raffle that has not opened yet
+    ///
+    /// Creates a lightweight `InterestRecord`, paid for by the user, and
+    /// counts it on the raffle so creators can gauge demand before launch.
+    pub fn register_interest(ctx: Context<RegisterInterest>) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let clock = Clock::get()?;
+
+        // Validation: Interest is only taken before sales open
+        require!(
+            raffle.state == RaffleState::Pending,
+            ErrorCode::RaffleNotPending
+        );
+
+        ctx.accounts.interest_record.set_inner(InterestRecord {
+            raffle: raffle.key(),
+            user: ctx.accounts.user.key(),
+            registered_at: clock.unix_timestamp,
+            bump: ctx.bumps.interest_record,
+        });
+
+        raffle.interest_count = raffle
+            .interest_count
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        msg!("{} registered interest in raffle {}", ctx.accounts.user.key(), raffle.raffle_id);
+
+        Ok(())
+    }
+
+    /// Opens sales of a pending raffle once `sale_start` is reached
+    ///
+    /// Permissionless, like `end_raffle`, so a scheduled launch does not
+    /// wait on the creator.
+    pub fn open_sales(ctx: Context<OpenSales>) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let clock = Clock::get()?;
+
+        // Validation: Only pending raffles past their sale start can open
+        require!(
+            raffle.state == RaffleState::Pending,
+            ErrorCode::RaffleNotPending
+        );
+        require!(
+            clock.unix_timestamp >= raffle.sale_start,
+            ErrorCode::SalesNotOpen
+        );
+
+        raffle.state = RaffleState::Active;
+        record_in_index(&mut ctx.accounts.raffle_index, raffle.key(), RaffleState::Active)?;
+
+        msg!(
+            "Raffle {} is open for sales ({} registered interest)",
+            raffle.raffle_id,
+            raffle.interest_count
+        );
+
+        Ok(())
+    }
+
+    /// Ends a raffle whose end time has passed
+    ///
+    /// Permissionless so an automation thread can close sales at
+    /// `end_time` without the creator being online.
+    pub fn end_raffle(ctx: Context<EndRaffle>) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let clock = Clock::get()?;
+
+        // Validation: Only active or paused raffles can be ended
+        require!(
+            raffle.state == RaffleState::Active
+                || raffle.state == RaffleState::PausedAtSoftCap,
+            ErrorCode::RaffleNotActive
+        );
+
+        // Validation: Raffle must be past its end time
+        require!(
+            clock.unix_timestamp >= raffle.end_time,
+            ErrorCode::RaffleNotEnded
+        );
+
+        raffle.mark_ended(clock.slot);
+        msg!("Raffle {} has ended (time expired)", raffle.raffle_id);
+
+        Ok(())
+    }
+
+    /// Draws a winner for an ended raffle and distributes prizes
+    ///
+    /// Uses the raffle's `randomness_source` to select `winners_count` winning tickets
+    /// from distinct buyers, weighted either per ticket or per lamport
+    /// contributed (see `SelectionMode`), and splits the winner share
+    /// equally between them.
+    /// If the winning ticket was converted to an NFT, the prize goes to the
+    /// NFT's current holder; the winning `Ticket`, the holder's token
+    /// account and the holder's wallet must then be in `remaining_accounts`.
+    /// Callable by any keeper (only the `draw_authority`, when set), who
+    /// receives the raffle's `keeper_reward` (capped at `max_keeper_tip`)
+    /// from the pot. The remainder is split 10% to creator, `charity_bps`
+    /// to the charity (if any) and the rest to the winners. The charity
+    /// account must be passed in `remaining_accounts` alongside the
+    /// ticket buyers, as must the SlotHashes sysvar for `SlotHashes` raffles.
+    /// With `allow_fallback` (which requires a `draw_authority`), a winner
+    /// whose account the draw authority did not pass is skipped for the next
+    /// eligible entry (wrapping) whose buyer's account was, and the swap is
+    /// recorded in `draw_fallbacks`. Otherwise a missing winner fails the
+    /// draw. With `require_gate_at_draw`, winners must also still hold
+    /// `gate_mint` in their associated token account, which must be in
+    /// `remaining_accounts` for every winner (and replacement) considered;
+    /// winners whose account is empty or closed are skipped the same way.
+    /// If every entry belongs to one buyer, the draw is a no contest: that
+    /// buyer gets the whole pot back, no fee is taken, and the raffle
+    /// completes without a winner. A fixed prize then returns to the
+    /// creator, passed as `raffle_creator`.
+    pub fn draw_winner<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DrawWinner<'info>>,
+    ) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let clock = Clock::get()?;
+
+        // Count the attempt (only persists if the draw succeeds, see
+        // `record_draw_attempt` for a log that survives reverts)
+        raffle.draw_attempts = raffle
+            .draw_attempts
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        emit!(DrawAttempted {
+            raffle: raffle.key(),
+            caller: ctx.accounts.keeper.key(),
+            attempt: raffle.draw_attempts,
+            slot: clock.slot,
+        });
+
+        // Validation: Raffle is ended (or just expired) and drawable now
+        raffle.begin_draw(&clock, &ctx.accounts.keeper.key())?;
+
+        // Validation: Raffles with separate prizes use draw_next_prize
+        require!(raffle.prizes.is_empty(), ErrorCode::PrizeRaffle);
+
+        // Validation: A draw started with draw_batch must be finished by it
+        require!(raffle.drawn_count == 0, ErrorCode::BatchDrawInProgress);
+
+        // No contest: one buyer holds every entry, so refund the pot without a fee
+        if raffle.is_no_contest() {
+            return settle_no_contest(ctx.accounts, ctx.remaining_accounts, &clock);
+        }
+
+        // Validation: Every winner must be a distinct participant
+        raffle.fit_winners_to_participants()?;
+
+        // Derive the seed from the raffle's randomness source
+        log_compute_units("winner selection");
+        let seed = draw_seed(raffle, clock.slot, ctx.remaining_accounts)?;
+        let mut winning_indices = select_winning_indices(raffle, seed)?;
+
+        msg!("Drawing winner for raffle {}", raffle.raffle_id);
+
+        // Advance past winners whose account was not passed, if allowed, or
+        // who no longer hold the gate token
+        let mut draw_fallbacks = Vec::new();
+        let check_accounts = raffle.allow_fallback
+            && !raffle.escrow_prize
+            && raffle.draw_authority == Some(ctx.accounts.keeper.key());
+        let check_gate = raffle.require_gate_at_draw;
+        if (check_accounts || check_gate) && raffle.tokenized_entries == 0 {
+            let mut supplied: Vec<Pubkey> =
+                ctx.remaining_accounts.iter().map(|acc| acc.key()).collect();
+            supplied.push(ctx.accounts.creator.key());
+            if let Some(winner) = &ctx.accounts.winner {
+                supplied.push(winner.key());
+            }
+            let remaining_accounts = ctx.remaining_accounts;
+            let gate_mint = raffle.gate_mint.filter(|_| check_gate);
+            let eligible = |buyer: &Pubkey| -> Result<bool> {
+                if check_accounts && !supplied.contains(buyer) {
+                    return Ok(false);
+                }
+                match gate_mint {
+                    Some(gate_mint) => holds_gate(remaining_accounts, buyer, &gate_mint),
+                    None => Ok(true),
+                }
+            };
+
+            let mut taken: Vec<Pubkey> = winning_indices
+                .iter()
+                .filter_map(|&index| raffle.ticket_buyers.get(index).copied())
+                .collect();
+            for winning_index in winning_indices.iter_mut() {
+                let buyer = *raffle
+                    .ticket_buyers
+                    .get(*winning_index)
+                    .ok_or(ErrorCode::InvalidWinningTicket)?;
+                if eligible(&buyer)? {
+                    continue;
+                }
+
+                let selected = fallback_index(raffle, *winning_index, &taken, eligible)?;
+                taken.push(raffle.ticket_buyers[selected]);
+                let fallback = DrawFallback {
+                    skipped_index: *winning_index as u32,
+                    selected_index: selected as u32,
+                };
+                msg!(
+                    "Winner ineligible, entry {} replaced by {}",
+                    fallback.skipped_index,
+                    fallback.selected_index
+                );
+                emit!(WinnerFallback {
+                    raffle: raffle.key(),
+                    skipped_index: fallback.skipped_index,
+                    selected_index: fallback.selected_index,
+                });
+                draw_fallbacks.push(fallback);
+                *winning_index = selected;
+            }
+        }
+
+        // Tokenized entries are won by whoever currently holds the ticket NFT.
+        // A single winner can instead be named by their `winning_ticket` PDA.
+        log_compute_units("winner resolution");
+        let mut winner_pubkeys = Vec::with_capacity(winning_indices.len());
+        for &winning_index in winning_indices.iter() {
+            let winner_pubkey = match &ctx.accounts.winning_ticket {
+                Some(ticket) if ticket.nft_mint.is_none() => {
+                    require!(winning_indices.len() == 1, ErrorCode::InvalidWinningTicket);
+                    let buyer =
+                        ticket_buyer_at(ticket, raffle.key(), winning_index, ctx.program_id)?;
+                    require!(
+                        ctx.accounts.winner.as_ref().map(|winner| winner.key()) == Some(buyer),
+                        ErrorCode::InvalidWinningTicket
+                    );
+                    buyer
+                }
+                _ => resolve_entry_owner(
+                    raffle,
+                    raffle.key(),
+                    winning_index,
+                    ctx.remaining_accounts,
+                )?,
+            };
+
+            msg!("Winning ticket index: {}", winning_index);
+            msg!("Winner: {}", winner_pubkey);
+            winner_pubkeys.push(winner_pubkey);
+        }
+
+        // Distribute everything above the rent, bond and fixed prize the raffle keeps
+        log_compute_units("distribution");
+        let raffle_balance =
+            distributable_pot(&raffle.to_account_info(), raffle.reserved_lamports())?;
+
+        // Calculate prize distribution: charity share, 10% to creator, rest to winners
+        let plan = compute_distribution(raffle, raffle_balance)?;
+        raffle.fixed_prize_settled = true;
+        let winner_prize = plan.winner_prize;
+        let creator_fee = plan.creator_fee;
+        let charity_amount = plan.charity_amount;
+
+        msg!("Pot: {} lamports", raffle_balance);
+
+        // Pay the keeper tip for triggering the draw
+        if plan.keeper_tip > 0 {
+            msg!("Keeper tip: {} lamports to {}", plan.keeper_tip, ctx.accounts.keeper.key());
+
+            transfer_from_raffle(
+                &raffle.to_account_info(),
+                &ctx.accounts.keeper.to_account_info(),
+                plan.keeper_tip,
+            )?;
+        }
+
+        msg!("Winner prize: {} lamports", winner_prize);
+        msg!("Creator fee: {} lamports", creator_fee);
+
+        // A winner passed as the fee recipient aliases the same lamports, so
+        // their share is credited together with the fee in a single transfer
+        let fee_recipient = ctx.accounts.creator.key();
+        let mut fee_recipient_credit = if raffle.escrow_fees { 0 } else { creator_fee };
+
+        // Transfer each winner's share of the prize, or keep it for `claim_prize`
+        if raffle.escrow_prize {
+            raffle.prize_owed = winner_prize;
+        } else {
+            for (winner_pubkey, share) in winner_pubkeys.iter().zip(plan.winner_shares.iter()) {
+                if *winner_pubkey == fee_recipient {
+                    fee_recipient_credit = fee_recipient_credit
+                        .checked_add(*share)
+                        .ok_or(ErrorCode::MathOverflow)?;
+                    continue;
+                }
+
+                // Use the `winner` account, or find it in remaining_accounts
+                let winner_account = match ctx
+                    .accounts
+                    .winner
+                    .as_ref()
+                    .filter(|winner| winner.key() == *winner_pubkey)
+                {
+                    Some(winner) => winner.to_account_info(),
+                    None => ctx.remaining_accounts
+                        .iter()
+                        .find(|acc| acc.key() == *winner_pubkey)
+                        .ok_or(ErrorCode::InvalidWinningTicket)?
+                        .clone(),
+                };
+
+                transfer_from_raffle(&raffle.to_account_info(), &winner_account, *share)?;
+            }
+        }
+
+        // Transfer charity share
+        if charity_amount > 0 {
+            let charity_pubkey = raffle.charity.ok_or(ErrorCode::InvalidCharity)?;
+            let charity_account = ctx.remaining_accounts
+                .iter()
+                .find(|acc| acc.key() == charity_pubkey)
+                .ok_or(ErrorCode::InvalidCharity)?;
+
+            msg!("Charity share: {} lamports to {}", charity_amount, charity_pubkey);
+
+            transfer_from_raffle(&raffle.to_account_info(), charity_account, charity_amount)?;
+        }
+
+        // Transfer the parent raffle's share into its pot
+        if plan.parent_share > 0 {
+            let parent_pubkey = raffle.parent.ok_or(ErrorCode::InvalidParent)?;
+            let parent_account = ctx.remaining_accounts
+                .iter()
+                .find(|acc| acc.key() == parent_pubkey)
+                .ok_or(ErrorCode::InvalidParent)?;
+
+            msg!("Parent share: {} lamports to {}", plan.parent_share, parent_pubkey);
+
+            transfer_from_raffle(&raffle.to_account_info(), parent_account, plan.parent_share)?;
+        }
+
+        // Transfer creator fee, or keep it in the raffle for `claim_fees`
+        if raffle.escrow_fees {
+            raffle.accrued_creator_fee = creator_fee;
+        }
+        if fee_recipient_credit > 0 {
+            transfer_from_raffle(
+                &raffle.to_account_info(),
+                &ctx.accounts.creator.to_account_info(),
+                fee_recipient_credit,
+            )?;
+        }
+
+        // Update raffle state
+        let winner_pubkey = winner_pubkeys[0];
+        raffle.winner = Some(winner_pubkey);
+        raffle.winners = winner_pubkeys;
+        raffle.winning_indices = winning_indices.iter().map(|&i| i as u32).collect();
+        raffle.draw_fallbacks = draw_fallbacks;
+        raffle.consolation_pool = plan.consolation_amount;
+        raffle.consolation_entries = raffle.non_winning_entries();
+        raffle.winner_prize = winner_prize;
+        raffle.draw_seed = seed;
+        raffle.draw_slot = clock.slot;
+        raffle.drawn_at = clock.unix_timestamp;
+        raffle.state = RaffleState::Completed;
+        record_in_index(&mut ctx.accounts.raffle_index, raffle.key(), RaffleState::Completed)?;
+        record_on_leaderboard(&mut ctx.accounts.leaderboard, raffle, winner_prize)?;
+        ctx.accounts.tvl_tracker.release(raffle.total_revenue)?;
+
+        // Free up an active raffle slot for the creator
+        let creator_state = &mut ctx.accounts.creator_state;
+        creator_state.active_raffle_count = creator_state
+            .active_raffle_count
+            .checked_sub(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        msg!(
+            "Winner drawn! {} wins {} lamports",
+            winner_pubkey,
+            winner_prize
+        );
+        log_compute_units("return");
+
+        Ok(())
+    }
+
+    /// Draws up to `count` of the raffle's winners
+    ///
+    /// Splits a large multi-winner `draw_winner` across transactions to stay
+    /// within compute limits. The first batch fixes `draw_seed` and pays the
+    /// keeper tip, charity share and creator fee; every batch pays its own
+    /// winners their share. Winners are selected exactly as in `draw_winner`,
+    /// so the finished draw can be checked with `verify_winner`. The raffle
+    /// stays `Ended`, so no tickets can be bought, until the last batch
+    /// completes it. Winner (and charity) accounts for the batch go in
+    /// `remaining_accounts`. A no contest is settled by the first batch
+    /// exactly as `draw_winner` settles it.
+    pub fn draw_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DrawWinner<'info>>,
+        count: u8,
+    ) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let clock = Clock::get()?;
+
+        // Validation: At least one winner per batch
+        require!(count > 0, ErrorCode::InvalidQuantity);
+
+        // Validation: Raffle is ended (or just expired) and drawable now
+        raffle.begin_draw(&clock, &ctx.accounts.keeper.key())?;
+
+        // Validation: Raffles with separate prizes use draw_next_prize
+        require!(raffle.prizes.is_empty(), ErrorCode::PrizeRaffle);
+
+        // Validation: Draw-time gate checks only run in draw_winner
+        require!(!raffle.require_gate_at_draw, ErrorCode::InvalidGate);
+
+        let start = raffle.drawn_count as usize;
+
+        // No contest: one buyer holds every entry, so refund the pot without a fee
+        if start == 0 && raffle.is_no_contest() {
+            return settle_no_contest(ctx.accounts, ctx.remaining_accounts, &clock);
+        }
+
+        if start == 0 {
+            // Validation: Every winner must be a distinct participant
+            raffle.fit_winners_to_participants()?;
+
+            // Fix the seed so every batch draws from the same sequence
+            raffle.draw_seed = draw_seed(raffle, clock.slot, ctx.remaining_accounts)?;
+            raffle.draw_slot = clock.slot;
+
+            let pot = distributable_pot(&raffle.to_account_info(), raffle.reserved_lamports())?;
+            let plan = compute_distribution(raffle, pot)?;
+            raffle.fixed_prize_settled = true;
+            raffle.batch_winner_prize = plan.winner_prize;
+            raffle.consolation_pool = plan.consolation_amount;
+
+            msg!("Pot: {} lamports", pot);
+
+            // Pay the keeper tip for starting the draw
+            if plan.keeper_tip > 0 {
+                transfer_from_raffle(
+                    &raffle.to_account_info(),
+                    &ctx.accounts.keeper.to_account_info(),
+                    plan.keeper_tip,
+                )?;
+            }
+
+            // Transfer charity share
+            if plan.charity_amount > 0 {
+                let charity_pubkey = raffle.charity.ok_or(ErrorCode::InvalidCharity)?;
+                let charity_account = ctx.remaining_accounts
+                    .iter()
+                    .find(|acc| acc.key() == charity_pubkey)
+                    .ok_or(ErrorCode::InvalidCharity)?;
+
+                transfer_from_raffle(
+                    &raffle.to_account_info(),
+                    charity_account,
+                    plan.charity_amount,
+                )?;
+            }
+
+            // Transfer the parent raffle's share into its pot
+            if plan.parent_share > 0 {
+                let parent_pubkey = raffle.parent.ok_or(ErrorCode::InvalidParent)?;
+                let parent_account = ctx.remaining_accounts
+                    .iter()
+                    .find(|acc| acc.key() == parent_pubkey)
+                    .ok_or(ErrorCode::InvalidParent)?;
+
+                transfer_from_raffle(
+                    &raffle.to_account_info(),
+                    parent_account,
+                    plan.parent_share,
+                )?;
+            }
+
+            // Transfer creator fee, or keep it in the raffle for `claim_fees`
+            if raffle.escrow_fees {
+                raffle.accrued_creator_fee = plan.creator_fee;
+            } else {
+                transfer_from_raffle(
+                    &raffle.to_account_info(),
+                    &ctx.accounts.creator.to_account_info(),
+                    plan.creator_fee,
+                )?;
+            }
+        }
+
+        let end = start
+            .saturating_add(count as usize)
+            .min(raffle.winners_count as usize);
+        let mut taken: Vec<usize> = raffle.winning_indices.iter().map(|&i| i as usize).collect();
+
+        for n in start..end {
+            let index = select_distinct_index(
+                raffle,
+                winner_seed(raffle.draw_seed, n as u64),
+                &taken,
+            )?;
+            let winner_pubkey = resolve_entry_owner(
+                raffle,
+                raffle.key(),
+                index,
+                ctx.remaining_accounts,
+            )?;
+            let share = winner_share(raffle.batch_winner_prize, raffle.winners_count, n)?;
+
+            // Keep an escrowed prize in the raffle for `claim_prize`
+            if raffle.escrow_prize {
+                raffle.prize_owed = share;
+            } else {
+                let winner_account = ctx.remaining_accounts
+                    .iter()
+                    .find(|acc| acc.key() == winner_pubkey)
+                    .ok_or(ErrorCode::InvalidWinningTicket)?;
+
+                transfer_from_raffle(&raffle.to_account_info(), winner_account, share)?;
+            }
+
+            msg!("Winner {}: {} wins {} lamports", n, winner_pubkey, share);
+
+            taken.push(index);
+            raffle.winning_indices.push(index as u32);
+            raffle.winners.push(winner_pubkey);
+        }
+
+        raffle.drawn_count = end as u8;
+
+        // Complete the raffle once every winner is drawn
+        if end == raffle.winners_count as usize {
+            raffle.winner = raffle.winners.first().copied();
+            raffle.consolation_entries = raffle.non_winning_entries();
+            raffle.winner_prize = raffle.batch_winner_prize;
+            raffle.drawn_at = clock.unix_timestamp;
+            raffle.state = RaffleState::Completed;
+            record_in_index(&mut ctx.accounts.raffle_index, raffle.key(), RaffleState::Completed)?;
+            let prize = raffle.batch_winner_prize;
+            record_on_leaderboard(&mut ctx.accounts.leaderboard, raffle, prize)?;
+            ctx.accounts.tvl_tracker.release(raffle.total_revenue)?;
+
+            // Free up an active raffle slot for the creator
+            let creator_state = &mut ctx.accounts.creator_state;
+            creator_state.active_raffle_count = creator_state
+                .active_raffle_count
+                .checked_sub(1)
+                .ok_or(ErrorCode::MathOverflow)?;
+
+            msg!("Batched draw of raffle {} completed", raffle.raffle_id);
+        }
+
+        Ok(())
+    }
+
+    /// Draws the winner of the raffle's next separate prize
+    ///
+    /// For raffles created with `prizes`, each call awards
+    /// `prizes[drawn_count]` to an entry from the shared pool. Unless
+    /// `allow_multiple_wins` is set, no buyer wins two prizes. Once every
+    /// prize is drawn the raffle completes; the ticket revenue stays in the
+    /// raffle for the creator, who supplies the prizes, to collect with
+    /// `close_raffle`.
+    pub fn draw_next_prize<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DrawNextPrize<'info>>,
+    ) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let clock = Clock::get()?;
+
+        // Validation: Raffle is ended (or just expired) and drawable now
+        raffle.begin_draw(&clock, &ctx.accounts.keeper.key())?;
+
+        // Validation: Only prize raffles with prizes left
+        require!(!raffle.prizes.is_empty(), ErrorCode::NotPrizeRaffle);
+        let prize_index = raffle.drawn_count as usize;
+        require!(prize_index < raffle.prizes.len(), ErrorCode::AllPrizesDrawn);
+
+        // Entries already holding a prize are excluded unless repeat wins are allowed
+        let taken: Vec<usize> = if raffle.allow_multiple_wins {
+            Vec::new()
+        } else {
+            require!(
+                prize_index < raffle.unique_buyers(),
+                ErrorCode::NotEnoughParticipants
+            );
+            raffle.prize_entry_indices.iter().map(|&i| i as usize).collect()
+        };
+
+        let seed = draw_seed(raffle, clock.slot, ctx.remaining_accounts)?;
+        let prize_seed = winner_seed(seed, prize_index as u64);
+        let entry_index = select_distinct_index(raffle, prize_seed, &taken)?;
+        let winner = resolve_entry_owner(
+            raffle,
+            raffle.key(),
+            entry_index,
+            ctx.remaining_accounts,
+        )?;
+
+        raffle.prize_entry_indices.push(entry_index as u32);
+        raffle.prize_winners.push(winner);
+        raffle.drawn_count = raffle
+            .drawn_count
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(PrizeDrawn {
+            raffle: raffle.key(),
+            prize_index: prize_index as u8,
+            winner,
+            entry_index: entry_index as u32,
+        });
+
+        msg!(
+            "Prize {} ({}) of raffle {} won by {}",
+            prize_index,
+            raffle.prizes[prize_index].label,
+            raffle.raffle_id,
+            winner
+        );
+
+        // Complete the raffle once every prize has a winner
+        if raffle.drawn_count as usize == raffle.prizes.len() {
+            raffle.winner = raffle.prize_winners.first().copied();
+            raffle.draw_slot = clock.slot;
+            raffle.drawn_at = clock.unix_timestamp;
+            raffle.state = RaffleState::Completed;
+            record_in_index(&mut ctx.accounts.raffle_index, raffle.key(), RaffleState::Completed)?;
+            // Separate prizes are delivered off-chain, so no lamports count as paid
+            record_on_leaderboard(&mut ctx.accounts.leaderboard, raffle, 0)?;
+            ctx.accounts.tvl_tracker.release(raffle.total_revenue)?;
+
+            // Free up an active raffle slot for the creator
+            let creator_state = &mut ctx.accounts.creator_state;
+            creator_state.active_raffle_count = creator_state
+                .active_raffle_count
+                .checked_sub(1)
+                .ok_or(ErrorCode::MathOverflow)?;
+        }
+
+        Ok(())
+    }
+
+    /// Logs an intent to draw in a persistent `DrawAttempt` record
+    ///
+    /// Keepers send this in its own transaction before `draw_winner`, so
+    /// the attempt stays on record even if the draw reverts. Repeated
+    /// records for one raffle reveal callers probing for favorable slots.
+    pub fn record_draw_attempt(ctx: Context<RecordDrawAttempt>) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let clock = Clock::get()?;
+
+        // Validation: Only raffles that can still be drawn
+        require!(
+            raffle.state == RaffleState::Active || raffle.state == RaffleState::Ended,
+            ErrorCode::RaffleNotActive
+        );
+
+        let attempt = &mut ctx.accounts.draw_attempt;
+        attempt.raffle = raffle.key();
+        attempt.caller = ctx.accounts.caller.key();
+        attempt.attempt = raffle.draw_attempts;
+        attempt.slot = clock.slot;
+        attempt.timestamp = clock.unix_timestamp;
+        attempt.bump = ctx.bumps.draw_attempt;
+
+        raffle.draw_attempts = raffle
+            .draw_attempts
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(DrawAttempted {
+            raffle: raffle.key(),
+            caller: attempt.caller,
+            attempt: raffle.draw_attempts,
+            slot: clock.slot,
+        });
+
+        msg!(
+            "Draw attempt #{} recorded for raffle {}",
+            raffle.draw_attempts,
+            raffle.raffle_id
+        );
+
+        Ok(())
+    }
+
+    /// Reveals the creator's secret for a commit-reveal raffle
+    ///
+    /// Allowed once sales are over. The secret must hash to the commitment
+    /// given at creation; `draw_winner` then mixes it with the draw slot.
+    pub fn reveal_randomness(ctx: Context<RevealRandomness>, secret: [u8; 32]) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let clock = Clock::get()?;
+
+        // Validation: Only commit-reveal raffles take a secret, once
+        require!(
+            raffle.randomness_source == RandomnessSource::CommitReveal,
+            ErrorCode::InvalidRandomnessSource
+        );
+        require!(
+            raffle.revealed_secret.is_none(),
+            ErrorCode::SecretAlreadyRevealed
+        );
+
+        // Validation: Reveal only after sales have closed
+        require!(
+            raffle.state == RaffleState::Ended || clock.unix_timestamp >= raffle.end_time,
+            ErrorCode::RaffleNotEnded
+        );
+
+        // Validation: Secret must match the commitment
+        let commitment = raffle.commitment.ok_or(ErrorCode::MissingCommitment)?;
+        require!(
+            hashv(&[&secret]).to_bytes() == commitment,
+            ErrorCode::InvalidReveal
+        );
+
+        raffle.revealed_secret = Some(secret);
+        msg!("Randomness revealed for raffle {}", raffle.raffle_id);
+
+        Ok(())
+    }
+
+    /// Publishes the committed beacon round's randomness for a beacon raffle
+    ///
+    /// Signed by the `beacon_signer` fixed at creation, which relays the
+    /// public beacon (e.g. a drand round). The round must be the one
+    /// committed at creation; `draw_winner` then derives the seed from it.
+    pub fn publish_beacon(
+        ctx: Context<PublishBeacon>,
+        round: u64,
+        value: [u8; 32],
+    ) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let clock = Clock::get()?;
+
+        // Validation: Only beacon raffles take a beacon value, once
+        require!(
+            raffle.randomness_source == RandomnessSource::Beacon,
+            ErrorCode::InvalidRandomnessSource
+        );
+        require!(
+            raffle.beacon_value.is_none(),
+            ErrorCode::BeaconAlreadyPublished
+        );
+
+        // Validation: Publish only after sales have closed
+        require!(
+            raffle.state == RaffleState::Ended || clock.unix_timestamp >= raffle.end_time,
+            ErrorCode::RaffleNotEnded
+        );
+
+        // Validation: The committed publisher relays the committed round
+        require!(
+            raffle.beacon_signer == Some(ctx.accounts.beacon_signer.key()),
+            ErrorCode::Unauthorized
+        );
+        require!(round == raffle.beacon_round, ErrorCode::BeaconRoundMismatch);
+
+        raffle.beacon_value = Some(value);
+        msg!("Beacon round {} published for raffle {}", round, raffle.raffle_id);
+
+        Ok(())
+    }
+
+    /// Recomputes a completed draw and checks it against the stored winners
+    ///
+    /// Replays the selection from the stored `draw_seed` with the same
+    /// algorithm `draw_winner` used. Returns true when the recomputed
+    /// entries match the stored ones and, for raffles without NFT tickets,
+    /// their buyers match the stored winners. For tokenized raffles the
+    /// winner is the NFT holder at draw time, so only the entries are checked.
+    /// Entries replaced by a fallback are checked against `draw_fallbacks`.
+    /// A no contest draws nothing, so it matches when no winner is stored.
+    pub fn verify_winner(ctx: Context<ReadRaffle>) -> Result<bool> {
+        let raffle = &ctx.accounts.raffle;
+
+        // Validation: Only completed draws can be verified
+        require!(
+            raffle.state == RaffleState::Completed,
+            ErrorCode::RaffleNotCompleted
+        );
+
+        // Validation: The replay uses the algorithm the raffle was drawn with
+        require!(
+            raffle.algo_version == DRAW_ALGO_VERSION,
+            ErrorCode::AlgoVersionMismatch
+        );
+
+        if raffle.termination_reason == Some(TerminationReason::NoContest) {
+            let matches = raffle.winner.is_none() && raffle.winning_indices.is_empty();
+            msg!("Winner verification for raffle {}: {} (no contest)", raffle.raffle_id, matches);
+
+            return Ok(matches);
+        }
+
+        let mut indices = select_winning_indices(raffle, raffle.draw_seed)?;
+        for index in indices.iter_mut() {
+            if let Some(fallback) = raffle
+                .draw_fallbacks
+                .iter()
+                .find(|fallback| fallback.skipped_index as usize == *index)
+            {
+                *index = fallback.selected_index as usize;
+            }
+        }
+        let indices_match = indices.len() == raffle.winning_indices.len()
+            && indices
+                .iter()
+                .zip(raffle.winning_indices.iter())
+                .all(|(&index, &stored)| index as u32 == stored);
+
+        let winners_match = raffle.tokenized_entries > 0
+            || indices
+                .iter()
+                .zip(raffle.winners.iter())
+                .all(|(&index, winner)| raffle.ticket_buyers.get(index) == Some(winner));
+
+        let matches = indices_match
+            && winners_match
+            && raffle.winner == raffle.winners.first().copied();
+        msg!("Winner verification for raffle {}: {}", raffle.raffle_id, matches);
+
+        Ok(matches)
+    }
+
+    /// Returns the roles `who` holds in the raffle as a bitmask
+    ///
+    /// Combines `ROLE_CREATOR`, `ROLE_WINNER` and `ROLE_PARTICIPANT` so a
+    /// client can gate its actions on a single answer.
+    pub fn my_role(ctx: Context<ReadRaffle>, who: Pubkey) -> Result<u8> {
+        let raffle = &ctx.accounts.raffle;
+        let mut role = 0;
+
+        if raffle.creator == who {
+            role |= ROLE_CREATOR;
+        }
+        if raffle.winner == Some(who) || raffle.winners.contains(&who) {
+            role |= ROLE_WINNER;
+        }
+        if raffle.ticket_buyers.contains(&who) {
+            role |= ROLE_PARTICIPANT;
+        }
+
+        Ok(role)
+    }
+
+    /// Returns the buyer who would win if the draw ran at `hypothetical_slot`
+    ///
+    /// Runs the same seed derivation and selection as `draw_winner` over the
+    /// current `ticket_buyers` and returns the first winner's buyer. Only
+    /// slot-dependent sources react to the slot; `SlotHashes` raffles need the
+    /// sysvar in `remaining_accounts` like a real draw.
+    pub fn winner_at_slot(ctx: Context<ReadRaffle>, hypothetical_slot: u64) -> Result<Pubkey> {
+        let raffle = &ctx.accounts.raffle;
+
+        let seed = draw_seed(raffle, hypothetical_slot, ctx.remaining_accounts)?;
+        let indices = select_winning_indices(raffle, seed)?;
+
+        let index = *indices.first().ok_or(ErrorCode::InvalidWinningTicket)?;
+        raffle
+            .ticket_buyers
+            .get(index)
+            .copied()
+            .ok_or(ErrorCode::InvalidWinningTicket.into())
+    }
+
+    /// Reports whether `buy_ticket` would currently accept a purchase
+    ///
+    /// Returns the same preconditions `buy_ticket` enforces, plus the
+    /// reason a purchase would be rejected.
+    pub fn is_purchasable(ctx: Context<ReadRaffle>) -> Result<PurchaseStatus> {
+        let clock = Clock::get()?;
+        let reason = ctx.accounts.raffle.purchase_block(clock.unix_timestamp);
+
+        Ok(PurchaseStatus {
+            purchasable: reason == PurchaseBlock::None,
+            reason,
+        })
+    }
+
+    /// Returns how many tickets `buyer` could still buy right now
+    ///
+    /// The smallest of the buyer's per-wallet allowance, the tickets left
+    /// before the raffle sells out and those fitting under the revenue cap
+    /// at the current undiscounted price; 0 while purchases are blocked.
+    /// `buyer_record` may be omitted for a buyer who has not entered yet.
+    pub fn buyer_remaining(ctx: Context<ReadBuyerRemaining>, buyer: Pubkey) -> Result<u32> {
+        let raffle = &ctx.accounts.raffle;
+        let clock = Clock::get()?;
+
+        if raffle.purchase_block(clock.unix_timestamp) != PurchaseBlock::None {
+            return Ok(0);
+        }
+
+        let tickets_bought = ctx
+            .accounts
+            .buyer_record
+            .as_ref()
+            .map_or(0, |record| record.tickets_bought);
+        let remaining = raffle
+            .wallet_allowance(tickets_bought)
+            .min(raffle.max_tickets.saturating_sub(raffle.total_tickets_sold))
+            .min(raffle.tickets_within_revenue_cap(raffle.base_price(clock.unix_timestamp)));
+        msg!("{} may buy {} more tickets", buyer, remaining);
+
+        Ok(remaining)
+    }
+
+    /// Returns what one ticket would cost `buyer` right now
+    ///
+    /// Applies the price curve and, when a discount token account is passed,
+    /// the holder discount, exactly as `buy_ticket` does.
+    pub fn current_price(ctx: Context<ReadCurrentPrice>, buyer: Pubkey) -> Result<u64> {
+        let clock = Clock::get()?;
+
+        ticket_price_for(
+            &ctx.accounts.raffle,
+            &ctx.accounts.discount_token_account,
+            buyer,
+            clock.unix_timestamp,
+        )
+    }
+
+    /// Previews how the current pot would be split if drawn now
+    ///
+    /// Uses the same distribution math as `draw_winner`.
+    pub fn preview_distribution(ctx: Context<ReadRaffle>) -> Result<DistributionPlan> {
+        let raffle = &ctx.accounts.raffle;
+        let pot = distributable_pot(&raffle.to_account_info(), raffle.reserved_lamports())?;
+
+        compute_distribution(raffle, pot)
+    }
+
+    /// Previews whether `account` would win a draw at the current slot
+    ///
+    /// Selects winners from the current entries exactly as `draw_winner`
+    /// would in this slot, and splits the current pot as
+    /// `preview_distribution` does, returning `account`'s share if it wins.
+    /// Entries converted to NFTs count for their buyer, and skipped winners
+    /// (`allow_fallback`, `require_gate_at_draw`) are not replaced. A no
+    /// contest has no winner. Fails wherever the creator's `draw_winner`
+    /// would (the draw authority's, if set), so a raffle the creator may
+    /// draw early is simulated before `end_time`. The SlotHashes sysvar goes
+    /// in `remaining_accounts` for `SlotHashes` raffles.
+    pub fn simulate_for(ctx: Context<ReadRaffle>, account: Pubkey) -> Result<DrawSimulation> {
+        let mut raffle = (*ctx.accounts.raffle).clone();
+        let clock = Clock::get()?;
+
+        // Validation: Only raffles draw_winner would draw right now
+        let caller = raffle.draw_authority.unwrap_or(raffle.creator);
+        raffle.begin_draw(&clock, &caller)?;
+        require!(raffle.prizes.is_empty(), ErrorCode::PrizeRaffle);
+        require!(raffle.drawn_count == 0, ErrorCode::BatchDrawInProgress);
+
+        let no_win = DrawSimulation { wins: false, prize: 0 };
+        if raffle.is_no_contest() {
+            return Ok(no_win);
+        }
+
+        raffle.fit_winners_to_participants()?;
+        let seed = draw_seed(&raffle, clock.slot, ctx.remaining_accounts)?;
+        let winning_indices = select_winning_indices(&raffle, seed)?;
+        let Some(position) = winning_indices
+            .iter()
+            .position(|&index| raffle.ticket_buyers.get(index) == Some(&account))
+        else {
+            return Ok(no_win);
+        };
+
+        let pot = distributable_pot(
+            &ctx.accounts.raffle.to_account_info(),
+            raffle.reserved_lamports(),
+        )?;
+        let plan = compute_distribution(&raffle, pot)?;
+
+        Ok(DrawSimulation {
+            wins: true,
+            prize: plan.winner_shares.get(position).copied().unwrap_or(0),
+        })
+    }
+
+    /// Returns the countdown header a raffle widget displays
+    ///
+    /// `seconds_remaining` is clamped at 0 once `end_time` has passed, and
+    /// `state` is the `RaffleState` discriminant.
+    pub fn ticker(ctx: Context<ReadRaffle>) -> Result<Ticker> {
+        let raffle = &ctx.accounts.raffle;
+        let clock = Clock::get()?;
+
+        Ok(Ticker {
+            tickets_remaining: raffle.max_tickets.saturating_sub(raffle.total_tickets_sold),
+            seconds_remaining: raffle.end_time.saturating_sub(clock.unix_timestamp).max(0),
+            pot: distributable_pot(&raffle.to_account_info(), raffle.reserved_lamports())?,
+            state: raffle.state.clone() as u8,
+        })
+    }
+
+    /// Returns the ticket revenue currently locked in unsettled raffles
+    pub fn get_tvl(ctx: Context<ReadTvl>) -> Result<u64> {
+        Ok(ctx.accounts.tvl_tracker.total_locked)
+    }
+
+    /// Returns the version of the draw algorithm the raffle uses
+    ///
+    /// Fixed at creation to `DRAW_ALGO_VERSION`, so a result can be
+    /// reproduced with the exact algorithm in effect for the raffle.
+    pub fn get_algo_version(ctx: Context<ReadRaffle>) -> Result<u8> {
+        Ok(ctx.accounts.raffle.algo_version)
+    }
+
+    /// Returns the canonical address and bump of a creator's raffle
+    ///
+    /// Uses the same seeds as `create_raffle`, so clients need not encode
+    /// them. Pots are held by the raffle account itself (token payments
+    /// are not supported yet), so there is no separate vault address.
+    pub fn derive_addresses(
+        ctx: Context<DeriveAddresses>,
+        raffle_id: u64,
+        creator: Pubkey,
+    ) -> Result<RaffleAddresses> {
+        let (raffle, bump) = Pubkey::find_program_address(
+            &[b"raffle", creator.as_ref(), raffle_id.to_le_bytes().as_ref()],
+            ctx.program_id,
+        );
+
+        Ok(RaffleAddresses { raffle, bump })
+    }
+
+    /// Checks that a ticket is a genuine entry of the raffle
+    ///
+    /// Re-derives the ticket PDA from `[b"ticket", raffle, entry_index]`
+    /// (the seeds `buy_ticket` uses; `ticket_number` adds the display
+    /// offset on top) and compares it with the ticket's address.
+    pub fn verify_ticket(ctx: Context<ReadTicket>) -> Result<TicketVerification> {
+        let raffle_key = ctx.accounts.raffle.key();
+        let ticket = &ctx.accounts.ticket;
+
+        let (expected, _) = Pubkey::find_program_address(
+            &[
+                b"ticket",
+                raffle_key.as_ref(),
+                ticket.entry_index.to_le_bytes().as_ref(),
+            ],
+            ctx.program_id,
+        );
+
+        Ok(TicketVerification {
+            address_matches: ticket.key() == expected,
+            raffle_matches: ticket.raffle == raffle_key,
+        })
+    }
+
+    /// Returns a versioned snapshot of the whole raffle account
+    ///
+    /// Layout: `DUMP_STATE_VERSION`, a byte set to 1 when `ticket_buyers`
+    /// and `entry_cumulative` are included, then the Borsh-serialized
+    /// `Raffle` (without the account discriminator). Both vectors are
+    /// emptied when the full snapshot would not fit in the return data.
+    pub fn dump_state(ctx: Context<ReadRaffle>) -> Result<Vec<u8>> {
+        let raffle: &Raffle = &ctx.accounts.raffle;
+
+        // The return data also carries the vector's 4-byte length prefix
+        let fits = |dump: &Vec<u8>| dump.len() + 4 <= MAX_RETURN_DATA;
+
+        let mut dump = vec![DUMP_STATE_VERSION, 1];
+        raffle.serialize(&mut dump)?;
+        if !fits(&dump) {
+            let mut trimmed = raffle.clone();
+            trimmed.ticket_buyers.clear();
+            trimmed.entry_cumulative.clear();
+
+            dump = vec![DUMP_STATE_VERSION, 0];
+            trimmed.serialize(&mut dump)?;
+
+            // Validation: Snapshot must fit even without the entries
+            require!(fits(&dump), ErrorCode::StateTooLarge);
+        }
+
+        Ok(dump)
+    }
+
+    /// Returns a page of the raffle's ticket buyers
+    ///
+    /// Yields `ticket_buyers[start..start + count]`, clamped to the entries
+    /// sold. `count` is capped at `MAX_BUYERS_PAGE`.
+    pub fn get_buyers(ctx: Context<ReadRaffle>, start: u32, count: u32) -> Result<Vec<Pubkey>> {
+        // Validation: Page must fit in the return data
+        require!(
+            count > 0 && count <= MAX_BUYERS_PAGE,
+            ErrorCode::InvalidPageSize
+        );
+
+        let buyers = &ctx.accounts.raffle.ticket_buyers;
+        let start = (start as usize).min(buyers.len());
+        let end = start.saturating_add(count as usize).min(buyers.len());
+
+        Ok(buyers[start..end].to_vec())
+    }
+
+    /// Summarizes how entries are spread across buyers
+    ///
+    /// Lists the `top_n` buyers holding the most entries (ties in order of
+    /// first purchase) and a Gini coefficient of entries per buyer, in bps:
+    /// 0 when every buyer holds the same number, approaching
+    /// `BPS_DENOMINATOR` as a few buyers hold most entries.
+    pub fn entry_distribution(ctx: Context<ReadRaffle>, top_n: u8) -> Result<EntryDistribution> {
+        // Validation: The list must fit in the return data
+        require!(
+            top_n > 0 && top_n <= MAX_TOP_BUYERS,
+            ErrorCode::InvalidPageSize
+        );
+
+        let mut holdings: Vec<BuyerEntries> = Vec::new();
+        for buyer in ctx.accounts.raffle.ticket_buyers.iter() {
+            match holdings.iter_mut().find(|holding| holding.buyer == *buyer) {
+                Some(holding) => holding.entries += 1,
+                None => holdings.push(BuyerEntries { buyer: *buyer, entries: 1 }),
+            }
+        }
+        holdings.sort_by_key(|holding| std::cmp::Reverse(holding.entries));
+
+        // With counts ranked ascending from 1:
+        // G = 2 * sum(rank * count) / (n * total) - (n + 1) / n
+        let n = holdings.len() as u128;
+        let total: u128 = holdings.iter().map(|holding| holding.entries as u128).sum();
+        let weighted: u128 = holdings
+            .iter()
+            .enumerate()
+            .map(|(k, holding)| (n - k as u128) * holding.entries as u128)
+            .sum();
+        let concentration_bps = if total == 0 {
+            0
+        } else {
+            ((2 * weighted - (n + 1) * total) * BPS_DENOMINATOR as u128 / (n * total)) as u16
+        };
+
+        let unique_buyers = holdings.len() as u32;
+        holdings.truncate(top_n as usize);
+
+        Ok(EntryDistribution {
+            unique_buyers,
+            top_buyers: holdings,
+            concentration_bps,
+        })
+    }
+
+    /// Returns the lamports an entrant has spent on the raffle's tickets
+    pub fn get_total_spent(ctx: Context<ReadBuyerRecord>) -> Result<u64> {
+        Ok(ctx.accounts.buyer_record.total_spent)
+    }
+
+    /// Returns how far into the sales window an entrant first bought, in bps
+    ///
+    /// 0 means they bought as sales opened and `BPS_DENOMINATOR` at
+    /// `end_time`, so early supporters can be rewarded off-chain.
+    pub fn get_entry_timing(ctx: Context<ReadEntryTiming>) -> Result<u16> {
+        let raffle = &ctx.accounts.raffle;
+        let opened_at = raffle.price_start_time.max(raffle.sale_start);
+        let window = raffle.end_time.saturating_sub(opened_at).max(1) as u128;
+        let elapsed = ctx
+            .accounts
+            .buyer_record
+            .first_purchase_time
+            .saturating_sub(opened_at)
+            .clamp(0, window as i64) as u128;
+
+        // Elapsed never exceeds the window, so the result fits in a u16
+        Ok((elapsed * BPS_DENOMINATOR as u128 / window) as u16)
+    }
+
+    /// Returns how many raffles the creator has created
+    ///
+    /// A creator numbering raffle ids from 0 lets clients derive every raffle
+    /// PDA from `0..created_count` instead of scanning the program.
+    pub fn get_created_count(ctx: Context<ReadCreatorState>) -> Result<u32> {
+        Ok(ctx.accounts.creator_state.created_count)
+    }
+
+    /// Withdraws escrowed creator fees from several completed raffles
+    ///
+    /// `remaining_accounts` holds (raffle, fee recipient) pairs, both
+    /// writable. Every `Raffle` must belong to the signing creator, and its
+    /// fee is paid to its `fee_recipient` (a reassigned recipient or the
+    /// creator's treasury), as `draw_winner` pays unescrowed fees. Raffles
+    /// with nothing accrued, or frozen by the admin pending a clawback, are
+    /// skipped.
+    pub fn claim_fees<'info>(ctx: Context<'_, '_, 'info, 'info, ClaimFees<'info>>) -> Result<()> {
+        let creator = ctx.accounts.creator.key();
+        let mut total_claimed: u64 = 0;
+
+        // Validation: Every raffle comes with its fee recipient
+        require!(
+            ctx.remaining_accounts.chunks_exact(2).remainder().is_empty(),
+            ErrorCode::InvalidFeeRecipient
+        );
+
+        for pair in ctx.remaining_accounts.chunks(2) {
+            let account_info = &pair[0];
+            let recipient_info = &pair[1];
+            let mut raffle = Account::<Raffle>::try_from(account_info)?;
+
+            // Validation: Only the signer's raffles can be claimed
+            require_keys_eq!(raffle.creator, creator, ErrorCode::Unauthorized);
+
+            // Validation: Fees go to whoever the raffle pays them to
+            require_keys_eq!(
+                recipient_info.key(),
+                raffle.fee_recipient,
+                ErrorCode::InvalidFeeRecipient
+            );
+
+            let fee = raffle.accrued_creator_fee;
+            if fee == 0 || raffle.frozen {
+                continue;
+            }
+
+            transfer_from_raffle(account_info, recipient_info, fee)?;
+
+            // Mark the fee claimed and persist the raffle
+            raffle.accrued_creator_fee = 0;
+            raffle.exit(ctx.program_id)?;
+
+            total_claimed = total_claimed
+                .checked_add(fee)
+                .ok_or(ErrorCode::MathOverflow)?;
+        }
+
+        msg!("Claimed {} lamports of creator fees", total_claimed);
+
+        Ok(())
+    }
+
+    /// Creates the signer's treasury collecting fees across their raffles
+    ///
+    /// Raffles created with `use_treasury` pay their creator fee into it at
+    /// draw time, so it can be swept at once with `withdraw_treasury`.
+    pub fn initialize_treasury(ctx: Context<InitializeTreasury>) -> Result<()> {
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.creator = ctx.accounts.creator.key();
+        treasury.bump = ctx.bumps.treasury;
+
+        msg!("Treasury initialized for {}", treasury.creator);
+
+        Ok(())
+    }
+
+    /// Sweeps the fees collected in the signer's treasury
+    ///
+    /// Leaves the treasury's rent so it keeps receiving fees.
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>) -> Result<()> {
+        let treasury_info = ctx.accounts.treasury.to_account_info();
+        let amount = distributable_pot(&treasury_info, 0)?;
+
+        transfer_from_raffle(&treasury_info, &ctx.accounts.creator.to_account_info(), amount)?;
+
+        msg!("Withdrew {} lamports from the treasury", amount);
+
+        Ok(())
+    }
+
+    /// Aggregates stats across the signer's raffles
+    ///
+    /// Every `Raffle` passed in `remaining_accounts` must belong to the
+    /// signing creator. Fees earned are the creator's share of ticket
+    /// revenue for completed raffles.
+    pub fn creator_stats<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReadCreatorRaffles<'info>>,
+    ) -> Result<CreatorStats> {
+        let creator = ctx.accounts.creator.key();
+        let mut stats = CreatorStats {
+            total_raffles: 0,
+            total_tickets_sold: 0,
+            total_revenue: 0,
+            total_fees_earned: 0,
+        };
+        let mut seen: Vec<Pubkey> = Vec::with_capacity(ctx.remaining_accounts.len());
+
+        for account_info in ctx.remaining_accounts.iter() {
+            let raffle = Account::<Raffle>::try_from(account_info)?;
+
+            // Validation: Only the signer's raffles, each counted once
+            require_keys_eq!(raffle.creator, creator, ErrorCode::Unauthorized);
+            require!(
+                !seen.contains(account_info.key),
+                ErrorCode::DuplicateRaffle
+            );
+            seen.push(account_info.key());
+
+            stats.total_raffles = stats
+                .total_raffles
+                .checked_add(1)
+                .ok_or(ErrorCode::MathOverflow)?;
+            stats.total_tickets_sold = stats
+                .total_tickets_sold
+                .checked_add(raffle.total_tickets_sold as u64)
+                .ok_or(ErrorCode::MathOverflow)?;
+            stats.total_revenue = stats
+                .total_revenue
+                .checked_add(raffle.total_revenue)
+                .ok_or(ErrorCode::MathOverflow)?;
+
+            if raffle.state == RaffleState::Completed {
+                let plan = compute_distribution(&raffle, raffle.total_revenue)?;
+                stats.total_fees_earned = stats
+                    .total_fees_earned
+                    .checked_add(plan.creator_fee)
+                    .ok_or(ErrorCode::MathOverflow)?;
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Opts the signing creator into the public leaderboard
+    ///
+    /// Creates the creator's `LeaderboardEntry`. Draws passing it as
+    /// `leaderboard` add each completed raffle's revenue and prize, so the
+    /// totals cover the raffles drawn after joining.
+    pub fn join_leaderboard(ctx: Context<JoinLeaderboard>) -> Result<()> {
+        let entry = &mut ctx.accounts.leaderboard;
+        entry.creator = ctx.accounts.creator.key();
+        entry.total_raffles_completed = 0;
+        entry.total_volume = 0;
+        entry.total_prizes_paid = 0;
+        entry.bump = ctx.bumps.leaderboard;
+
+        msg!("Creator {} joined the leaderboard", entry.creator);
+
+        Ok(())
+    }
+
+    /// Returns a creator's leaderboard totals
+    pub fn leaderboard_stats(ctx: Context<ReadLeaderboard>) -> Result<LeaderboardStats> {
+        let entry = &ctx.accounts.leaderboard;
+        Ok(LeaderboardStats {
+            creator: entry.creator,
+            total_raffles_completed: entry.total_raffles_completed,
+            total_volume: entry.total_volume,
+            total_prizes_paid: entry.total_prizes_paid,
+        })
+    }
+
+    /// Marks an expired, under-subscribed raffle as failed
+    ///
+    /// Callable by anyone once `end_time` has passed without reaching
+    /// `min_tickets`, `min_unique_buyers` or `min_pot`. Enables
+    /// `refund_ticket` for every buyer.
+    pub fn mark_failed(ctx: Context<MarkFailed>) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let clock = Clock::get()?;
+
+        // Validation: Only running raffles can fail
+        require!(
+            raffle.state == RaffleState::Active
+                || raffle.state == RaffleState::PausedAtSoftCap
+                || raffle.state == RaffleState::Ended,
+            ErrorCode::RaffleNotActive
+        );
+
+        // Validation: Raffle must be past its end time
+        require!(
+            clock.unix_timestamp >= raffle.end_time,
+            ErrorCode::RaffleNotEnded
+        );
+
+        // Validation: Minimum participation or pot was not reached
+        let too_few_buyers = (raffle.unique_buyers() as u32) < raffle.min_unique_buyers;
+        require!(
+            raffle.total_tickets_sold < raffle.min_tickets
+                || too_few_buyers
+                || raffle.total_revenue < raffle.min_pot,
+            ErrorCode::MinTicketsMet
+        );
+
+        let reason = if raffle.total_tickets_sold < raffle.min_tickets {
+            TerminationReason::MinNotMet
+        } else if too_few_buyers {
+            TerminationReason::TooFewBuyers
+        } else {
+            TerminationReason::PotBelowMinimum
+        };
+
+        raffle.state = RaffleState::Failed;
+        raffle.termination_reason = Some(reason.clone());
+        record_in_index(&mut ctx.accounts.raffle_index, raffle.key(), RaffleState::Failed)?;
+
+        // Return the creator's fixed prize, kept apart from the buyers' refunds
+        let fixed_prize_refunded = raffle.held_fixed_prize();
+        if fixed_prize_refunded > 0 {
+            transfer_from_raffle(
+                &raffle.to_account_info(),
+                &ctx.accounts.creator.to_account_info(),
+                fixed_prize_refunded,
+            )?;
+            raffle.fixed_prize_settled = true;
+        }
+
+        // Free up an active raffle slot for the creator
+        let creator_state = &mut ctx.accounts.creator_state;
+        creator_state.active_raffle_count = creator_state
+            .active_raffle_count
+            .checked_sub(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(RaffleFailed {
+            raffle: raffle.key(),
+            raffle_id: raffle.raffle_id,
+            tickets_sold: raffle.total_tickets_sold,
+            min_tickets: raffle.min_tickets,
+            reason,
+            fixed_prize_refunded,
+        });
+
+        msg!(
+            "Raffle {} failed: {} of {} minimum tickets sold",
+            raffle.raffle_id,
+            raffle.total_tickets_sold,
+            raffle.min_tickets
+        );
+
+        Ok(())
+    }
+
+    /// Refunds a ticket of a failed raffle to its buyer
+    ///
+    /// Returns the amount paid for the ticket and closes the
+    /// ticket account, returning its rent to the buyer. The amount is the
+    /// entry's own payment recorded at purchase, not the current price, so
+    /// refunds under a price curve or discounts add up to the revenue.
+    ///
+    /// Apart from `cancel_raffle` during cooling off, this is the only
+    /// refund path: tickets of a live raffle cannot be withdrawn, and a
+    /// sold-out raffle ends and freezes its entries at once, so no capacity
+    /// is ever freed for a waitlist to take over.
+    pub fn refund_ticket(ctx: Context<RefundTicket>) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+
+        // Validation: Refunds are only available for failed raffles
+        require!(
+            raffle.state == RaffleState::Failed,
+            ErrorCode::RaffleNotFailed
+        );
+
+        // Validation: The refund goes to the ticket owner (NFT holder if tokenized)
+        let ticket = &ctx.accounts.ticket;
+        match ticket.nft_mint {
+            Some(nft_mint) => {
+                let holder_account = ctx
+                    .accounts
+                    .holder_token_account
+                    .as_ref()
+                    .ok_or(ErrorCode::InvalidTicketNft)?;
+                require!(
+                    holder_account.mint == nft_mint
+                        && holder_account.owner == ctx.accounts.buyer.key()
+                        && holder_account.amount == 1,
+                    ErrorCode::InvalidTicketNft
+                );
+            }
+            None => require!(
+                ticket.buyer == ctx.accounts.buyer.key(),
+                ErrorCode::Unauthorized
+            ),
+        }
+
+        let refund = raffle.entry_amount(ticket.entry_index as usize)?;
+
+        transfer_from_raffle(
+            &raffle.to_account_info(),
+            &ctx.accounts.buyer.to_account_info(),
+            refund,
+        )?;
+        ctx.accounts.tvl_tracker.release(refund)?;
+
+        raffle.tickets_refunded = raffle
+            .tickets_refunded
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        msg!(
+            "Ticket #{} refunded {} lamports to {}",
+            ctx.accounts.ticket.ticket_number,
+            refund,
+            ctx.accounts.buyer.key()
+        );
+
+        Ok(())
+    }
+
+    /// Converts a ticket into a transferable NFT receipt
+    ///
+    /// Mints a single token from a mint derived from the ticket, creates its
+    /// Metaplex metadata, removes the mint authority so supply stays at one,
+    /// and optionally freezes the holder's token account to make the
+    /// receipt soulbound. The metadata is named after the ticket number and
+    /// lists the raffle as its verified creator, so NFT tooling shows which
+    /// raffle and ticket the receipt stands for. From then on the NFT holder
+    /// owns the entry for draws and refunds.
+    ///
+    /// # Arguments
+    /// * `soulbound` - Freeze the NFT in the buyer's token account
+    /// * `uri` - Off-chain JSON describing the receipt (at most 200 bytes)
+    pub fn mint_ticket_nft(
+        ctx: Context<MintTicketNft>,
+        soulbound: bool,
+        uri: String,
+    ) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let ticket = &mut ctx.accounts.ticket;
+
+        // Validation: Each ticket maps to exactly one NFT
+        require!(ticket.nft_mint.is_none(), ErrorCode::TicketAlreadyTokenized);
+
+        // Validation: The URI must fit in the metadata account
+        require!(uri.len() <= MAX_URI_LENGTH, ErrorCode::InvalidMetadataUri);
+
+        // Validation: The draw-time gate is checked against the original buyers
+        require!(!raffle.require_gate_at_draw, ErrorCode::InvalidGate);
+
+        let raffle_id_bytes = raffle.raffle_id.to_le_bytes();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"raffle",
+            raffle.creator.as_ref(),
+            raffle_id_bytes.as_ref(),
+            &[raffle.bump],
+        ]];
+
+        // Mint the single receipt token to the buyer
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::MintTo {
+                    mint: ctx.accounts.ticket_mint.to_account_info(),
+                    to: ctx.accounts.buyer_token_account.to_account_info(),
+                    authority: raffle.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            1,
+        )?;
+
+        // Describe the receipt for NFT tooling while the raffle is still the
+        // mint authority
+        metadata::create_metadata_accounts_v3(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                metadata::CreateMetadataAccountsV3 {
+                    metadata: ctx.accounts.metadata.to_account_info(),
+                    mint: ctx.accounts.ticket_mint.to_account_info(),
+                    mint_authority: raffle.to_account_info(),
+                    payer: ctx.accounts.buyer.to_account_info(),
+                    update_authority: raffle.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            DataV2 {
+                name: format!("Raffle Ticket #{}", ticket.ticket_number),
+                symbol: TICKET_NFT_SYMBOL.to_string(),
+                uri,
+                seller_fee_basis_points: 0,
+                creators: Some(vec![Creator {
+                    address: raffle.key(),
+                    verified: true,
+                    share: 100,
+                }]),
+                collection: None,
+                uses: None,
+            },
+            false,
+            true,
+            None,
+        )?;
+
+        // Fix supply at one
+        token::set_authority(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::SetAuthority {
+                    current_authority: raffle.to_account_info(),
+                    account_or_mint: ctx.accounts.ticket_mint.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+
+        if soulbound {
+            token::freeze_account(CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::FreezeAccount {
+                    account: ctx.accounts.buyer_token_account.to_account_info(),
+                    mint: ctx.accounts.ticket_mint.to_account_info(),
+                    authority: raffle.to_account_info(),
+                },
+                signer_seeds,
+            ))?;
+        }
+
+        ticket.nft_mint = Some(ctx.accounts.ticket_mint.key());
+        raffle.tokenized_entries = raffle
+            .tokenized_entries
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        msg!(
+            "Ticket #{} of raffle {} minted as NFT {}",
+            ticket.ticket_number,
+            raffle.raffle_id,
+            ctx.accounts.ticket_mint.key()
+        );
+
+        Ok(())
+    }
+
+    /// Confirms prize delivery and releases the creator's bond (winner only)
+    ///
+    /// Must be called within `BOND_DELIVERY_WINDOW` of the draw.
+    pub fn confirm_delivery(ctx: Context<ConfirmDelivery>) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let clock = Clock::get()?;
+
+        // Validation: Only the winner of a completed draw can confirm
+        require!(
+            raffle.state == RaffleState::Completed,
+            ErrorCode::RaffleNotCompleted
+        );
+        require!(
+            raffle.winner == Some(ctx.accounts.winner.key()),
+            ErrorCode::Unauthorized
+        );
+
+        let bond = raffle.held_bond();
+        require!(bond > 0, ErrorCode::NoBond);
+
+        // Validation: Confirmation is only accepted inside the delivery window
+        let deadline = raffle
+            .drawn_at
+            .checked_add(BOND_DELIVERY_WINDOW)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            clock.unix_timestamp <= deadline,
+            ErrorCode::DeliveryWindowClosed
+        );
+
+        transfer_from_raffle(
+            &raffle.to_account_info(),
+            &ctx.accounts.creator.to_account_info(),
+            bond,
+        )?;
+
+        raffle.bond_released = true;
+
+        msg!("Delivery confirmed! Bond of {} lamports released to creator", bond);
+
+        Ok(())
+    }
+
+    /// Proves the winner holds the creator's off-chain claim code (winner only)
+    ///
+    /// The code must hash to the `claim_code` commitment given at creation,
+    /// linking the handover of a physical prize to the on-chain winner.
+    pub fn reveal_claim_code(ctx: Context<RevealClaimCode>, code: [u8; 32]) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let winner = ctx.accounts.winner.key();
+
+        // Validation: Only a winner of a completed draw can claim, once
+        require!(
+            raffle.state == RaffleState::Completed,
+            ErrorCode::RaffleNotCompleted
+        );
+        require!(raffle.winners.contains(&winner), ErrorCode::Unauthorized);
+        require!(!raffle.claim_code_revealed, ErrorCode::ClaimCodeAlreadyRevealed);
+
+        // Validation: Code must match the commitment
+        let commitment = raffle.claim_code.ok_or(ErrorCode::MissingCommitment)?;
+        require!(
+            hashv(&[&code]).to_bytes() == commitment,
+            ErrorCode::InvalidReveal
+        );
+
+        raffle.claim_code_revealed = true;
+
+        emit!(ClaimCodeRevealed {
+            raffle: raffle.key(),
+            winner,
+        });
+
+        msg!("Claim code of raffle {} revealed by {}", raffle.raffle_id, winner);
+
+        Ok(())
+    }
+
+    /// Withdraws up to `amount` of an escrowed prize (winner only)
+    ///
+    /// Raffles created with `escrow_prize` keep the winner's prize in the
+    /// raffle after the draw; the winner may withdraw it in one go or in
+    /// installments, as long as the total stays within what is owed. With
+    /// vesting, only the tranches unlocked so far can be withdrawn.
+    pub fn claim_prize(ctx: Context<ClaimPrize>, amount: u64) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let clock = Clock::get()?;
+
+        // Validation: Only the winner of a completed draw can claim
+        require!(
+            raffle.state == RaffleState::Completed,
+            ErrorCode::RaffleNotCompleted
+        );
+        require!(
+            raffle.winner == Some(ctx.accounts.winner.key()),
+            ErrorCode::Unauthorized
+        );
+
+        // Validation: Claims never exceed the prize still owed
+        require!(amount > 0, ErrorCode::InvalidQuantity);
+        require!(amount <= raffle.unclaimed_prize(), ErrorCode::OverClaim);
+
+        // Validation: Only the vested part of the prize can be withdrawn
+        let claimable = raffle
+            .vested_prize(clock.unix_timestamp)?
+            .saturating_sub(raffle.claimed_so_far);
+        require!(amount <= claimable, ErrorCode::PrizeNotVested);
+
+        transfer_from_raffle(
+            &raffle.to_account_info(),
+            &ctx.accounts.winner.to_account_info(),
+            amount,
+        )?;
+
+        raffle.claimed_so_far = raffle
+            .claimed_so_far
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        msg!(
+            "Claimed {} lamports of prize, {} remaining",
+            amount,
+            raffle.unclaimed_prize()
+        );
+
+        Ok(())
+    }
+
+    /// Pays a non-winning buyer their part of the consolation pool
+    ///
+    /// Raffles created with `consolation_bps` set that share of the pot
+    /// aside at the draw, split between buyers who did not win in
+    /// proportion to the entries they bought. Each buyer claims once.
+    pub fn claim_consolation(ctx: Context<ClaimConsolation>) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let record = &mut ctx.accounts.buyer_record;
+        let buyer = ctx.accounts.buyer.key();
+
+        // Validation: Only a drawn raffle with a consolation pool pays out
+        require!(
+            raffle.state == RaffleState::Completed,
+            ErrorCode::RaffleNotCompleted
+        );
+        require!(
+            raffle.consolation_pool > 0 && raffle.consolation_entries > 0,
+            ErrorCode::NoConsolation
+        );
+
+        // Validation: Winners get no consolation, and each buyer claims once
+        require!(!raffle.winners.contains(&buyer), ErrorCode::NoConsolation);
+        require!(!record.consolation_claimed, ErrorCode::ConsolationClaimed);
+
+        let amount = (raffle.consolation_pool as u128)
+            .checked_mul(record.tickets_bought as u128)
+            .and_then(|product| product.checked_div(raffle.consolation_entries as u128))
+            .and_then(|amount| u64::try_from(amount).ok())
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        transfer_from_raffle(
+            &raffle.to_account_info(),
+            &ctx.accounts.buyer.to_account_info(),
+            amount,
+        )?;
+
+        record.consolation_claimed = true;
+        raffle.consolation_entries_claimed = raffle
+            .consolation_entries_claimed
+            .checked_add(record.tickets_bought)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        msg!("{} claimed {} lamports of consolation", buyer, amount);
+
+        Ok(())
+    }
+
+    /// Pays a buyer their part of an `admin_clawback` refund pool
+    ///
+    /// The pool is split between all buyers in proportion to the entries
+    /// they bought. Each buyer claims once.
+    pub fn claim_clawback_refund(ctx: Context<ClaimClawbackRefund>) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let record = &mut ctx.accounts.buyer_record;
+        let buyer = ctx.accounts.buyer.key();
+
+        // Validation: Only a raffle with a clawback pays out, once per buyer
+        require!(raffle.clawback_pool > 0, ErrorCode::NothingToClawBack);
+        require!(!record.clawback_claimed, ErrorCode::ClawbackClaimed);
+
+        let amount = (raffle.clawback_pool as u128)
+            .checked_mul(record.tickets_bought as u128)
+            .and_then(|product| product.checked_div(raffle.total_tickets_sold as u128))
+            .and_then(|amount| u64::try_from(amount).ok())
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        transfer_from_raffle(
+            &raffle.to_account_info(),
+            &ctx.accounts.buyer.to_account_info(),
+            amount,
+        )?;
+
+        record.clawback_claimed = true;
+        raffle.clawback_entries_claimed = raffle
+            .clawback_entries_claimed
+            .checked_add(record.tickets_bought)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(ClawbackRefundClaimed {
+            raffle: raffle.key(),
+            buyer,
+            amount,
+        });
+
+        msg!("{} claimed {} lamports of clawed back fees", buyer, amount);
+
+        Ok(())
+    }
+
+    /// Enters a child raffle's winner into its parent raffle
+    ///
+    /// Permissionless once the child is drawn; the caller pays the rent of
+    /// the parent ticket and of the winner's parent buyer record, if new.
+    /// Each child advances its winner once, while the parent could still
+    /// sell a ticket: it must not be frozen, sold out or past `end_time`.
+    /// An entry that sells the parent out ends it, as a purchase would.
+    pub fn advance_winner(ctx: Context<AdvanceWinner>) -> Result<()> {
+        let child = &mut ctx.accounts.child;
+        let parent = &mut ctx.accounts.parent;
+        let clock = Clock::get()?;
+
+        // Validation: Only a drawn child advances, and only once
+        require!(
+            child.state == RaffleState::Completed,
+            ErrorCode::RaffleNotCompleted
+        );
+        require!(!child.winner_advanced, ErrorCode::WinnerAlreadyAdvanced);
+        let winner = child.winner.ok_or(ErrorCode::InvalidWinningTicket)?;
+
+        // Validation: The parent still takes entries, as for a purchase
+        parent.require_purchasable(clock.unix_timestamp)?;
+
+        let ticket = record_entry(parent, winner, 0, clock.unix_timestamp, ctx.bumps.ticket)?;
+        ctx.accounts.ticket.set_inner(ticket);
+        record_purchase(
+            &mut ctx.accounts.buyer_record,
+            parent.key(),
+            winner,
+            1,
+            0,
+            clock.unix_timestamp,
+            ctx.bumps.buyer_record,
+        )?;
+        child.winner_advanced = true;
+
+        msg!("{} advanced from raffle {} to raffle {}", winner, child.raffle_id, parent.raffle_id);
+
+        // Check if the parent should end (max tickets reached) or pause (soft cap)
+        parent.settle_sales_state(clock.slot);
+
+        Ok(())
+    }
+
+    /// Slashes an unconfirmed bond to the winner
+    ///
+    /// Callable by anyone once `BOND_DELIVERY_WINDOW` has passed since the
+    /// draw without the winner confirming delivery.
+    pub fn slash_bond(ctx: Context<SlashBond>) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let clock = Clock::get()?;
+
+        // Validation: Only completed draws, paid to the recorded winner
+        require!(
+            raffle.state == RaffleState::Completed,
+            ErrorCode::RaffleNotCompleted
+        );
+        require!(
+            raffle.winner == Some(ctx.accounts.winner.key()),
+            ErrorCode::Unauthorized
+        );
+
+        let bond = raffle.held_bond();
+        require!(bond > 0, ErrorCode::NoBond);
+
+        // Validation: The winner keeps the full window to confirm
+        let deadline = raffle
+            .drawn_at
+            .checked_add(BOND_DELIVERY_WINDOW)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            clock.unix_timestamp > deadline,
+            ErrorCode::DeliveryWindowOpen
+        );
+
+        transfer_from_raffle(
+            &raffle.to_account_info(),
+            &ctx.accounts.winner.to_account_info(),
+            bond,
+        )?;
+
+        raffle.bond_released = true;
+
+        msg!("Bond of {} lamports slashed to winner {}", bond, ctx.accounts.winner.key());
+
+        Ok(())
+    }
+
+    /// Seals the outcome of a completed raffle in a `ResultCertificate`
+    ///
+    /// Permissionless; the caller pays the certificate's rent. The PDA can
+    /// only be created once and no instruction modifies it, so it outlives
+    /// `close_raffle` as a standalone proof of the draw. `draw_seed` lets
+    /// anyone recompute the winners as `verify_winner` does.
+    pub fn finalize_result(ctx: Context<FinalizeResult>) -> Result<()> {
+        let raffle = &ctx.accounts.raffle;
+        let clock = Clock::get()?;
+
+        // Validation: Only a drawn raffle has an outcome to certify
+        require!(
+            raffle.state == RaffleState::Completed,
+            ErrorCode::RaffleNotCompleted
+        );
+
+        let certificate = &mut ctx.accounts.certificate;
+        certificate.raffle = raffle.key();
+        certificate.raffle_id = raffle.raffle_id;
+        certificate.winners = raffle.winners.clone();
+        certificate.prize = raffle.winner_prize;
+        certificate.draw_slot = raffle.draw_slot;
+        certificate.draw_seed = raffle.draw_seed;
+        certificate.total_participants = raffle.unique_buyers() as u32;
+        certificate.total_tickets = raffle.total_tickets_sold;
+        certificate.sealed_at = clock.unix_timestamp;
+        certificate.sealed = true;
+        certificate.bump = ctx.bumps.certificate;
+
+        msg!("Result of raffle {} sealed", raffle.raffle_id);
+
+        Ok(())
+    }
+
+    /// Closes a completed raffle once its bond and prize are settled, or a
+    /// failed one once every ticket has been refunded
+    ///
+    /// Returns the raffle account's rent to the creator, along with the bond
+    /// of a failed raffle. A parent closes only after all its child raffles,
+    /// which pass it as `parent_raffle` when they close.
+    pub fn close_raffle(ctx: Context<CloseRaffle>) -> Result<()> {
+        let raffle = &ctx.accounts.raffle;
+
+        // Validation: Only settled raffles can be closed
+        require!(
+            raffle.state == RaffleState::Failed || raffle.state == RaffleState::Completed,
+            ErrorCode::RaffleNotFailed
+        );
+
+        // Validation: Open children still pay into and advance to this raffle
+        require!(raffle.child_count == 0, ErrorCode::ChildRafflesOpen);
+        release_from_parent(raffle, &mut ctx.accounts.parent_raffle)?;
+
+        if raffle.state == RaffleState::Failed {
+            // Validation: All buyers must have been refunded first
+            require!(
+                raffle.tickets_refunded == raffle.total_tickets_sold,
+                ErrorCode::RefundsOutstanding
+            );
+        } else {
+            // Validation: The winner's claim on the bond must be settled first
+            require!(raffle.held_bond() == 0, ErrorCode::BondOutstanding);
+
+            // Validation: An escrowed prize must be fully claimed first
+            require!(raffle.unclaimed_prize() == 0, ErrorCode::PrizeUnclaimed);
+
+            // Validation: Every non-winner must have claimed their consolation
+            require!(
+                raffle.consolation_pool == 0
+                    || raffle.consolation_entries_claimed >= raffle.consolation_entries,
+                ErrorCode::ConsolationUnclaimed
+            );
+
+            // Validation: So must every buyer their clawback refund
+            require!(
+                raffle.clawback_pool == 0
+                    || raffle.clawback_entries_claimed >= raffle.total_tickets_sold,
+                ErrorCode::ClawbackUnclaimed
+            );
+        }
+
+        msg!("Raffle {} closed", raffle.raffle_id);
+
+        Ok(())
+    }
+
+    /// Finalizes an expired raffle that sold no tickets
+    ///
+    /// Callable by anyone once `end_time` has passed. Skips the draw, frees
+    /// the creator's active raffle slot and closes the raffle account,
+    /// returning its rent to the creator.
+    pub fn finalize_empty(ctx: Context<FinalizeEmpty>) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let clock = Clock::get()?;
+
+        // Validation: Only raffles that are still running or awaiting a draw
+        require!(
+            raffle.state == RaffleState::Active
+                || raffle.state == RaffleState::Ended
+                || raffle.state == RaffleState::Pending,
+            ErrorCode::RaffleNotActive
+        );
+
+        // Validation: Raffle must be past its end time
+        require!(
+            clock.unix_timestamp >= raffle.end_time,
+            ErrorCode::RaffleNotEnded
+        );
+
+        // Validation: Nothing was sold, so there is nothing to draw or refund
+        require!(raffle.total_tickets_sold == 0, ErrorCode::RaffleNotEmpty);
+
+        // Validation: Open children still pay into and advance to this raffle
+        require!(raffle.child_count == 0, ErrorCode::ChildRafflesOpen);
+        release_from_parent(raffle, &mut ctx.accounts.parent_raffle)?;
+
+        raffle.state = RaffleState::Completed;
+
+        // Free up an active raffle slot for the creator
+        let creator_state = &mut ctx.accounts.creator_state;
+        creator_state.active_raffle_count = creator_state
+            .active_raffle_count
+            .checked_sub(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        msg!("Raffle {} finalized with no tickets sold", raffle.raffle_id);
+
+        Ok(())
+    }
+
+    /// Rescinds a raffle that has not been drawn (creator only)
+    ///
+    /// Until `cooling_off_end` the raffle can be cancelled even with tickets
+    /// sold: every entry is refunded and its ticket closed, with
+    /// `remaining_accounts` holding each entry's ticket followed by its
+    /// buyer's wallet, in entry order. Afterwards only a raffle without
+    /// sales can be cancelled. The raffle account is closed, returning its
+    /// rent, bond and fixed prize to the creator.
+    pub fn cancel_raffle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CancelRaffle<'info>>,
+    ) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        let raffle_key = raffle.key();
+        let clock = Clock::get()?;
+
+        // Validation: Only raffles that have not been drawn or failed
+        require!(
+            raffle.state == RaffleState::Active
+                || raffle.state == RaffleState::Ended
+                || raffle.state == RaffleState::PausedAtSoftCap
+                || raffle.state == RaffleState::Pending,
+            ErrorCode::RaffleNotActive
+        );
+        require!(raffle.drawn_count == 0, ErrorCode::BatchDrawInProgress);
+
+        // Validation: Open children still pay into and advance to this raffle
+        require!(raffle.child_count == 0, ErrorCode::ChildRafflesOpen);
+        release_from_parent(raffle, &mut ctx.accounts.parent_raffle)?;
+
+        // Validation: Sold raffles can only be rescinded while cooling off
+        let entries = raffle.total_tickets_sold as usize;
+        if entries > 0 {
+            require!(
+                clock.unix_timestamp < raffle.cooling_off_end,
+                ErrorCode::CoolingOffEnded
+            );
+            require!(raffle.tokenized_entries == 0, ErrorCode::InvalidTicketNft);
+        }
+
+        // Validation: A ticket and its buyer's wallet per entry
+        require!(
+            ctx.remaining_accounts.len() >= entries * 2,
+            ErrorCode::InvalidTicketAccount
+        );
+
+        for (index, pair) in ctx.remaining_accounts.chunks(2).take(entries).enumerate() {
+            let ticket = Account::<Ticket>::try_from(&pair[0])?;
+            let buyer_info = &pair[1];
+
+            // Validation: Tickets are passed in entry order, each with its buyer
+            require!(
+                ticket.raffle == raffle_key && ticket.entry_index as usize == index,
+                ErrorCode::InvalidTicketAccount
+            );
+            require_keys_eq!(buyer_info.key(), ticket.buyer, ErrorCode::Unauthorized);
+
+            let refund = raffle.entry_amount(index)?;
+            transfer_from_raffle(&raffle.to_account_info(), buyer_info, refund)?;
+            ticket.close(buyer_info.clone())?;
+        }
+
+        raffle.termination_reason = Some(TerminationReason::CreatorCancelled);
+        ctx.accounts.tvl_tracker.release(raffle.total_revenue)?;
+
+        // Free up an active raffle slot for the creator
+        let creator_state = &mut ctx.accounts.creator_state;
+        creator_state.active_raffle_count = creator_state
+            .active_raffle_count
+            .checked_sub(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(RaffleCancelled {
+            raffle: raffle_key,
+            raffle_id: raffle.raffle_id,
+            tickets_refunded: raffle.total_tickets_sold,
+            reason: TerminationReason::CreatorCancelled,
+        });
+
+        msg!(
+            "Raffle {} cancelled, {} tickets refunded",
+            raffle.raffle_id,
+            raffle.total_tickets_sold
+        );
+
+        Ok(())
+    }
+
+    /// Moves every entry of an under-subscribed `source` raffle into `target`
+    ///
+    /// Both raffles must be active, belong to the signing creator, and share
+    /// a ticket price and selection mode. For each source entry, in order,
+    /// `remaining_accounts` holds the source ticket followed by the PDA of
+    /// the target ticket it becomes. Then, for each distinct source buyer in
+    /// order of first entry, it holds their source buyer record, the PDA of
+    /// their target record, which is created if needed, and their wallet.
+    /// Entry revenue moves to the target; the source tickets and raffle are
+    /// closed, returning their rent (and any bond) to the creator, who pays
+    /// the new tickets' and records' rent. Each source record is closed to
+    /// its buyer once copied.
+    pub fn merge_raffles<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MergeRaffles<'info>>,
+    ) -> Result<()> {
+        let source = &mut ctx.accounts.source;
+        let target = &mut ctx.accounts.target;
+        let source_key = source.key();
+        let target_key = target.key();
+        let clock = Clock::get()?;
+
+        // Validation: Two distinct raffles, both still selling
+        require_keys_neq!(source_key, target_key, ErrorCode::DuplicateRaffle);
+        require!(
+            source.state == RaffleState::Active && target.state == RaffleState::Active,
+            ErrorCode::RaffleNotActive
+        );
+
+        // Validation: An admin freeze on either raffle blocks the merge
+        require!(!source.frozen && !target.frozen, ErrorCode::RaffleFrozen);
+
+        // Validation: Entries must carry the same weight and ownership rules
+        require!(
+            source.ticket_price == target.ticket_price
+                && source.selection_mode == target.selection_mode
+                && source.tokenized_entries == 0
+                && source.one_per_wallet == target.one_per_wallet
+                && source.min_unique_buyers <= target.min_unique_buyers
+                && target.consolation_bps == 0
+                && source.parent.is_none()
+                && target.parent.is_none(),
+            ErrorCode::IncompatibleRaffles
+        );
+
+        // Validation: Open children still pay into and advance to the source
+        require!(source.child_count == 0, ErrorCode::ChildRafflesOpen);
+
+        // Validation: Combined entries fit the target and its storage
+        let source_entries = source.total_tickets_sold as usize;
+        let combined = target
+            .total_tickets_sold
+            .checked_add(source.total_tickets_sold)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            combined <= target.max_tickets && combined <= target.entry_capacity,
+            ErrorCode::MergeTooLarge
+        );
+
+        // Validation: Combined revenue stays within the target's cap
+        if target.max_total_revenue > 0 {
+            let combined_revenue = target
+                .total_revenue
+                .checked_add(source.total_revenue)
+                .ok_or(ErrorCode::MathOverflow)?;
+            require!(
+                combined_revenue <= target.max_total_revenue,
+                ErrorCode::RevenueCapReached
+            );
+        }
+
+        // Validation: A source ticket and a target ticket PDA per entry
+        require!(
+            ctx.remaining_accounts.len() >= source_entries * 2,
+            ErrorCode::InvalidTicketAccount
+        );
+
+        let space = 8 + Ticket::INIT_SPACE;
+        let rent = Rent::get()?.minimum_balance(space);
+
+        for (index, pair) in ctx.remaining_accounts.chunks(2).take(source_entries).enumerate() {
+            let source_ticket = Account::<Ticket>::try_from(&pair[0])?;
+            let target_info = &pair[1];
+
+            // Validation: Source tickets are passed in entry order
+            require!(
+                source_ticket.raffle == source_key && source_ticket.entry_index as usize == index,
+                ErrorCode::InvalidTicketAccount
+            );
+
+            let entry_index = target.total_tickets_sold.to_le_bytes();
+            let (expected_ticket, bump) = Pubkey::find_program_address(
+                &[b"ticket", target_key.as_ref(), entry_index.as_ref()],
+                ctx.program_id,
+            );
+
+            // Validation: Account must be the PDA of the target's next ticket number
+            require_keys_eq!(
+                target_info.key(),
+                expected_ticket,
+                ErrorCode::InvalidTicketAccount
+            );
+
+            let signer_seeds: &[&[&[u8]]] =
+                &[&[b"ticket", target_key.as_ref(), entry_index.as_ref(), &[bump]]];
+
+            anchor_lang::system_program::create_account(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::CreateAccount {
+                        from: ctx.accounts.creator.to_account_info(),
+                        to: target_info.clone(),
+                    },
+                    signer_seeds,
+                ),
+                rent,
+                space as u64,
+                ctx.program_id,
+            )?;
+
+            let amount = source.entry_amount(index)?;
+            let ticket = record_entry(
+                target,
+                source_ticket.buyer,
+                amount,
+                source_ticket.purchase_time,
+                bump,
+            )?;
+            ticket.try_serialize(&mut &mut target_info.try_borrow_mut_data()?[..])?;
+
+            source_ticket.close(ctx.accounts.creator.to_account_info())?;
+        }
+
+        // Carry each source buyer's record over to the target
+        let mut buyers: Vec<Pubkey> = Vec::new();
+        for buyer in source.ticket_buyers.iter() {
+            if !buyers.contains(buyer) {
+                buyers.push(*buyer);
+            }
+        }
+
+        // Validation: A source record, a target record PDA and a wallet per buyer
+        let record_accounts = &ctx.remaining_accounts[source_entries * 2..];
+        require!(
+            record_accounts.len() >= buyers.len() * 3,
+            ErrorCode::InvalidBuyerRecord
+        );
+
+        let record_space = 8 + BuyerRecord::INIT_SPACE;
+        let record_rent = Rent::get()?.minimum_balance(record_space);
+
+        for (buyer, accounts) in buyers.iter().zip(record_accounts.chunks(3)) {
+            let source_record = Account::<BuyerRecord>::try_from(&accounts[0])?;
+            let target_info = &accounts[1];
+            let buyer_info = &accounts[2];
+
+            // Validation: Source records are passed in buyer order, with their buyer
+            require!(
+                source_record.raffle == source_key
+                    && source_record.buyer == *buyer
+                    && buyer_info.key() == *buyer,
+                ErrorCode::InvalidBuyerRecord
+            );
+
+            let (expected_record, bump) = Pubkey::find_program_address(
+                &[b"buyer_record", target_key.as_ref(), buyer.as_ref()],
+                ctx.program_id,
+            );
+
+            // Validation: Account must be the buyer's record PDA on the target
+            require_keys_eq!(
+                target_info.key(),
+                expected_record,
+                ErrorCode::InvalidBuyerRecord
+            );
+
+            let mut record = if target_info.data_is_empty() {
+                let signer_seeds: &[&[&[u8]]] =
+                    &[&[b"buyer_record", target_key.as_ref(), buyer.as_ref(), &[bump]]];
+
+                anchor_lang::system_program::create_account(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.system_program.to_account_info(),
+                        anchor_lang::system_program::CreateAccount {
+                            from: ctx.accounts.creator.to_account_info(),
+                            to: target_info.clone(),
+                        },
+                        signer_seeds,
+                    ),
+                    record_rent,
+                    record_space as u64,
+                    ctx.program_id,
+                )?;
+
+                BuyerRecord {
+                    raffle: target_key,
+                    buyer: *buyer,
+                    tickets_bought: 0,
+                    nonce: 0,
+                    bump,
+                    consolation_claimed: false,
+                    total_spent: 0,
+                    first_purchase_time: source_record.first_purchase_time,
+                    clawback_claimed: false,
+                }
+            } else {
+                Account::<BuyerRecord>::try_from(target_info)?.into_inner()
+            };
+
+            record.tickets_bought = record
+                .tickets_bought
+                .checked_add(source_record.tickets_bought)
+                .ok_or(ErrorCode::MathOverflow)?;
+            record.total_spent = record
+                .total_spent
+                .checked_add(source_record.total_spent)
+                .ok_or(ErrorCode::MathOverflow)?;
+            record.first_purchase_time = record
+                .first_purchase_time
+                .min(source_record.first_purchase_time);
+
+            // Validation: A wallet in both one-per-wallet raffles keeps one entry
+            require!(
+                record.tickets_bought <= target.wallet_allowance(0),
+                ErrorCode::AlreadyEntered
+            );
+
+            record.try_serialize(&mut &mut target_info.try_borrow_mut_data()?[..])?;
+
+            // Return the source record's rent to its buyer
+            source_record.close(buyer_info.clone())?;
+        }
+
+        // Move the entries' revenue; the source's remaining lamports go to the creator
+        let revenue = source.total_revenue;
+
+        transfer_from_raffle(&source.to_account_info(), &target.to_account_info(), revenue)?;
+
+        // Free up the source raffle's active slot
+        let creator_state = &mut ctx.accounts.creator_state;
+        creator_state.active_raffle_count = creator_state
+            .active_raffle_count
+            .checked_sub(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        msg!(
+            "Merged {} entries from raffle {} into raffle {}",
+            source_entries,
+            source.raffle_id,
+            target.raffle_id
+        );
+
+        // Check if the target should end (max tickets reached) or pause (soft cap)
+        target.settle_sales_state(clock.slot);
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Logs the compute units left before `section` of an instruction
+///
+/// Only built with the `compute-log` feature, so integrators can profile
+/// large draws without the extra logs costing units in production.
+#[cfg(feature = "compute-log")]
+pub fn log_compute_units(section: &str) {
+    msg!("Compute units before {}:", section);
+    anchor_lang::solana_program::log::sol_log_compute_units();
+}
+
+#[cfg(not(feature = "compute-log"))]
+pub fn log_compute_units(_section: &str) {}
+
+/// Derives the `raffle_id` of a creation keyed by `idempotency_key`
+///
+/// Uses the key's first 8 bytes (little-endian), so every retry with the
+/// same key resolves to the same raffle PDA.
+pub fn idempotent_raffle_id(idempotency_key: &[u8; 16]) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&idempotency_key[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// Appends `raffle` to `index` if one was passed
+///
+/// The index must be the one recording `state`.
+pub fn record_in_index(
+    index: &mut Option<Account<RaffleIndex>>,
+    raffle: Pubkey,
+    state: RaffleState,
+) -> Result<()> {
+    let Some(index) = index else {
+        return Ok(());
+    };
+
+    require!(index.state == state, ErrorCode::InvalidRaffleIndex);
+    index.push(raffle);
+
+    Ok(())
+}
+
+/// Releases a closing child raffle's place on its parent's `child_count`
+///
+/// Does nothing for a raffle without a parent; otherwise the parent must
+/// be passed as `parent_raffle`.
+pub fn release_from_parent(
+    raffle: &Raffle,
+    parent_raffle: &mut Option<Account<Raffle>>,
+) -> Result<()> {
+    let Some(parent_key) = raffle.parent else {
+        return Ok(());
+    };
+
+    let parent = parent_raffle.as_mut().ok_or(ErrorCode::InvalidParent)?;
+    require_keys_eq!(parent.key(), parent_key, ErrorCode::InvalidParent);
+    parent.child_count = parent
+        .child_count
+        .checked_sub(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    Ok(())
+}
+
+/// Adds a completed raffle to its creator's leaderboard entry, if passed
+///
+/// Does nothing without a `LeaderboardEntry`, as the leaderboard is opt-in.
+pub fn record_on_leaderboard(
+    entry: &mut Option<Account<LeaderboardEntry>>,
+    raffle: &Raffle,
+    prizes_paid: u64,
+) -> Result<()> {
+    let Some(entry) = entry else {
+        return Ok(());
+    };
+
+    require_keys_eq!(entry.creator, raffle.creator, ErrorCode::InvalidLeaderboard);
+    entry.total_raffles_completed = entry
+        .total_raffles_completed
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    entry.total_volume = entry
+        .total_volume
+        .checked_add(raffle.total_revenue)
+        .ok_or(ErrorCode::MathOverflow)?;
+    entry.total_prizes_paid = entry
+        .total_prizes_paid
+        .checked_add(prizes_paid)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    Ok(())
+}
+
+/// Settles a no contest draw, where one buyer holds every entry
+///
+/// Shared by `draw_winner` and `draw_batch`. The buyer, who must be in
+/// `remaining_accounts`, gets the whole pot back without any fee, tip or
+/// split, a fixed prize returns to `raffle_creator`, and the raffle
+/// completes without a winner.
+pub fn settle_no_contest<'info>(
+    accounts: &mut DrawWinner<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    clock: &Clock,
+) -> Result<()> {
+    let raffle = &mut accounts.raffle;
+
+    let buyer = *raffle
+        .ticket_buyers
+        .first()
+        .ok_or(ErrorCode::InvalidWinningTicket)?;
+    let buyer_account = remaining_accounts
+        .iter()
+        .find(|acc| acc.key() == buyer)
+        .ok_or(ErrorCode::InvalidWinningTicket)?;
+
+    let refund = distributable_pot(&raffle.to_account_info(), raffle.reserved_lamports())?;
+
+    transfer_from_raffle(&raffle.to_account_info(), buyer_account, refund)?;
+
+    // The creator's fixed prize goes back to them (not the fee
+    // recipient), as nobody won it
+    let fixed_prize = raffle.held_fixed_prize();
+    if fixed_prize > 0 {
+        let raffle_creator = accounts
+            .raffle_creator
+            .as_ref()
+            .ok_or(ErrorCode::RaffleCreatorRequired)?;
+        transfer_from_raffle(
+            &raffle.to_account_info(),
+            &raffle_creator.to_account_info(),
+            fixed_prize,
+        )?;
+        raffle.fixed_prize_settled = true;
+    }
+
+    raffle.draw_slot = clock.slot;
+    raffle.drawn_at = clock.unix_timestamp;
+    raffle.state = RaffleState::Completed;
+    raffle.termination_reason = Some(TerminationReason::NoContest);
+    record_in_index(&mut accounts.raffle_index, raffle.key(), RaffleState::Completed)?;
+    accounts.tvl_tracker.release(raffle.total_revenue)?;
+
+    // Free up an active raffle slot for the creator
+    let creator_state = &mut accounts.creator_state;
+    creator_state.active_raffle_count = creator_state
+        .active_raffle_count
+        .checked_sub(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    emit!(NoContest {
+        raffle: raffle.key(),
+        buyer,
+        refund,
+        reason: TerminationReason::NoContest,
+    });
+
+    msg!("No contest! {} refunded {} lamports", buyer, refund);
+
+    Ok(())
+}
+
+/// Fails unless every share taken out of the pot fits within 100%
+///
+/// Totals the creator fee with `shares` (the charity share, the consolation
+/// pool and the parent's share), which `compute_distribution` takes before
+/// the winners' prize.
+pub fn require_splits_within_total(shares: &[u16]) -> Result<()> {
+    let total = [CREATOR_FEE_BPS]
+        .iter()
+        .chain(shares)
+        .try_fold(0u64, |total, &bps| total.checked_add(bps as u64))
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(total <= BPS_DENOMINATOR, ErrorCode::SplitsExceedTotal);
+
+    Ok(())
+}
+
+/// Moves `amount` lamports from a program-owned account (a raffle or a
+/// treasury) to `destination`
+///
+/// Debits then credits with checked math, and asserts the two balances sum
+/// to the same total afterwards so no lamports are created or lost.
+pub fn transfer_from_raffle(
+    raffle_info: &AccountInfo,
+    destination: &AccountInfo,
+    amount: u64,
+) -> Result<()> {
+    let total_before = (raffle_info.lamports() as u128) + (destination.lamports() as u128);
+
+    **raffle_info.try_borrow_mut_lamports()? = raffle_info
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    **destination.try_borrow_mut_lamports()? = destination
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let total_after = (raffle_info.lamports() as u128) + (destination.lamports() as u128);
+    require!(total_after == total_before, ErrorCode::LamportsNotConserved);
+
+    Ok(())
+}
+
+/// Returns `bps` basis points of `amount`, rounded down
+pub fn bps_of(amount: u64, bps: u16) -> Result<u64> {
+    let share = (amount as u128)
+        .checked_mul(bps as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        / BPS_DENOMINATOR as u128;
+    Ok(share as u64)
+}
+
+/// Returns the ticket price a buyer pays, applying the holder discount
+///
+/// The discount applies only when a token account for the raffle's
+/// `discount_mint`, owned by the buyer and holding a balance, is provided.
+pub fn ticket_price_for(
+    raffle: &Raffle,
+    discount_token_account: &Option<Account<TokenAccount>>,
+    buyer: Pubkey,
+    now: i64,
+) -> Result<u64> {
+    let base_price = raffle.base_price(now);
+    let Some(token_account) = discount_token_account else {
+        return Ok(base_price);
+    };
+
+    let discount_mint = raffle.discount_mint.ok_or(ErrorCode::InvalidDiscount)?;
+    require!(
+        token_account.mint == discount_mint,
+        ErrorCode::InvalidDiscountToken
+    );
+    require!(token_account.owner == buyer, ErrorCode::InvalidDiscountToken);
+    require!(token_account.amount > 0, ErrorCode::InvalidDiscountToken);
+
+    base_price
+        .checked_sub(bps_of(base_price, raffle.discount_bps)?)
+        .ok_or(ErrorCode::MathOverflow.into())
+}
+
+/// Checks the snapshot eligibility attestation of a gated raffle
+///
+/// The raffle's `snapshot_attestor` co-signs a purchase to attest that the
+/// entrant held the required asset at `snapshot_slot`. The holding check
+/// itself happens off-chain against historical state.
+pub fn require_snapshot_attestation(
+    raffle: &Raffle,
+    snapshot_attestor: &Option<Signer>,
+) -> Result<()> {
+    let Some(expected) = raffle.snapshot_attestor else {
+        return Ok(());
+    };
+
+    let attestor = snapshot_attestor
+        .as_ref()
+        .ok_or(ErrorCode::SnapshotAttestationRequired)?;
+    require_keys_eq!(
+        attestor.key(),
+        expected,
+        ErrorCode::SnapshotAttestationRequired
+    );
+
+    Ok(())
+}
+
+/// Records a paid entry on the raffle and returns the new ticket's data
+///
+/// Appends the buyer and running revenue, stamps the purchase sequence and
+/// emits `TicketPurchased`. The caller stores the returned ticket.
+pub fn record_entry(
+    raffle: &mut Account<Raffle>,
+    buyer: Pubkey,
+    price: u64,
+    now: i64,
+    bump: u8,
+) -> Result<Ticket> {
+    // Validation: Entries are frozen once the raffle has ended
+    require!(!raffle.buyers_frozen, ErrorCode::BuyersFrozen);
+
+    // Validation: The raffle account has room for another entry
+    require!(
+        raffle.total_tickets_sold < raffle.entry_capacity,
+        ErrorCode::RaffleAtCapacity
+    );
+
+    let ticket = Ticket {
+        raffle: raffle.key(),
+        buyer,
+        // Displayed number, shifted by the raffle's offset
+        ticket_number: raffle
+            .total_tickets_sold
+            .checked_add(raffle.ticket_number_offset)
+            .ok_or(ErrorCode::MathOverflow)?,
+        purchase_time: now,
+        bump,
+        // Stamp the ticket with the raffle's monotonic purchase sequence
+        global_sequence: raffle.purchase_sequence,
+        nft_mint: None,
+        entry_index: raffle.total_tickets_sold,
+        amount_paid: price,
+    };
+
+    raffle.purchase_sequence = raffle
+        .purchase_sequence
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    // Add buyer to the ticket_buyers vector
+    raffle.ticket_buyers.push(buyer);
+
+    // Track revenue and the running contribution total for this entry
+    raffle.total_revenue = raffle
+        .total_revenue
+        .checked_add(price)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let cumulative = raffle.total_revenue;
+    raffle.entry_cumulative.push(cumulative);
+
+    // Increment ticket count
+    raffle.total_tickets_sold = raffle
+        .total_tickets_sold
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    // Invariant: exactly one entry per ticket sold, so no buyer's odds drift
+    require!(
+        raffle.ticket_buyers.len() == raffle.total_tickets_sold as usize
+            && raffle.entry_cumulative.len() == raffle.ticket_buyers.len(),
+        ErrorCode::EntryCountMismatch
+    );
+
+    emit!(TicketPurchased {
+        raffle: ticket.raffle,
+        buyer,
+        ticket_number: ticket.ticket_number,
+        global_sequence: ticket.global_sequence,
+        purchase_time: now,
+        verified: raffle.verified,
+    });
+
+    Ok(ticket)
+}
+
+/// Tracks a purchase of `tickets` entries costing `spent` lamports on a buyer record
+///
+/// Initializes the record on first use, stamping the entrant's first
+/// purchase at `now`, and advances its nonce once per purchase instruction.
+pub fn record_purchase(
+    record: &mut Account<BuyerRecord>,
+    raffle: Pubkey,
+    buyer: Pubkey,
+    tickets: u32,
+    spent: u64,
+    now: i64,
+    bump: u8,
+) -> Result<()> {
+    if record.first_purchase_time == 0 {
+        record.first_purchase_time = now;
+    }
+    record.raffle = raffle;
+    record.buyer = buyer;
+    record.bump = bump;
+    record.tickets_bought = record
+        .tickets_bought
+        .checked_add(tickets)
+        .ok_or(ErrorCode::MathOverflow)?;
+    record.total_spent = record
+        .total_spent
+        .checked_add(spent)
+        .ok_or(ErrorCode::MathOverflow)?;
+    record.nonce = record
+        .nonce
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    Ok(())
+}
+
+/// Returns the raffle's lamports above its rent-exempt minimum and `reserved`
+///
+/// The raffle account keeps its rent after a draw so the result stays
+/// on-chain until the creator closes it. `reserved` covers lamports held
+/// for other purposes, such as an unresolved creator bond.
+pub fn distributable_pot(raffle_info: &AccountInfo, reserved: u64) -> Result<u64> {
+    let rent = Rent::get()?.minimum_balance(raffle_info.data_len());
+
+    raffle_info
+        .lamports()
+        .checked_sub(rent)
+        .and_then(|balance| balance.checked_sub(reserved))
+        .ok_or(ErrorCode::MathOverflow.into())
+}
+
+/// Splits `pot` between the keeper, the winner, the creator, the charity,
+/// the consolation pool and the parent raffle's share
+///
+/// The keeper tip comes off the top. The winner share of the remainder is
+/// rounded down and the creator receives any dust. `pot` excludes the
+/// creator's fixed prize, which is added to the winner prize untouched.
+pub fn compute_distribution(raffle: &Raffle, pot: u64) -> Result<DistributionPlan> {
+    let keeper_tip = raffle.keeper_tip().min(pot);
+    let remaining = pot
+        .checked_sub(keeper_tip)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let charity_amount = bps_of(remaining, raffle.charity_bps)?;
+    let consolation_amount = bps_of(remaining, raffle.consolation_bps)?;
+    let parent_share = bps_of(remaining, raffle.parent_share_bps)?;
+
+    let winner_bps = (BPS_DENOMINATOR as u16)
+        .checked_sub(CREATOR_FEE_BPS)
+        .and_then(|bps| bps.checked_sub(raffle.charity_bps))
+        .and_then(|bps| bps.checked_sub(raffle.consolation_bps))
+        .and_then(|bps| bps.checked_sub(raffle.parent_share_bps))
+        .ok_or(ErrorCode::MathOverflow)?;
+    let revenue_prize = bps_of(remaining, winner_bps)?;
+
+    let creator_fee = remaining
+        .checked_sub(revenue_prize)
+        .and_then(|rest| rest.checked_sub(charity_amount))
+        .and_then(|rest| rest.checked_sub(consolation_amount))
+        .and_then(|rest| rest.checked_sub(parent_share))
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    // The creator's fixed prize goes to the winners on top of their share
+    let winner_prize = revenue_prize
+        .checked_add(raffle.held_fixed_prize())
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let winner_shares = (0..raffle.winners_count.max(1) as usize)
+        .map(|n| winner_share(winner_prize, raffle.winners_count, n))
+        .collect::<Result<Vec<u64>>>()?;
+
+    Ok(DistributionPlan {
+        pot,
+        keeper_tip,
+        winner_prize,
+        winner_shares,
+        creator_fee,
+        charity: raffle.charity,
+        charity_amount,
+        consolation_amount,
+        parent_share,
+    })
+}
+
+/// Returns the `n`-th winner's part of `winner_prize`
+///
+/// The prize is split equally, the first winner keeps the dust.
+pub fn winner_share(winner_prize: u64, winners_count: u8, n: usize) -> Result<u64> {
+    let winners = winners_count.max(1) as u64;
+    let share = winner_prize / winners;
+    if n > 0 {
+        return Ok(share);
+    }
+
+    winner_prize
+        .checked_sub(share * (winners - 1))
+        .ok_or(ErrorCode::MathOverflow.into())
+}
+
+/// Returns the buyer recorded on `ticket`, the raffle's Ticket PDA for `index`
+///
+/// Re-derives the PDA from the ticket's seeds and bump, so the winner is
+/// tied to the entry drawn rather than to an account the caller picked.
+pub fn ticket_buyer_at(
+    ticket: &Account<Ticket>,
+    raffle_key: Pubkey,
+    index: usize,
+    program_id: &Pubkey,
+) -> Result<Pubkey> {
+    let entry_index = u32::try_from(index).map_err(|_| ErrorCode::InvalidWinningTicket)?;
+    let expected = Pubkey::create_program_address(
+        &[
+            b"ticket",
+            raffle_key.as_ref(),
+            entry_index.to_le_bytes().as_ref(),
+            &[ticket.bump],
+        ],
+        program_id,
+    )
+    .map_err(|_| ErrorCode::InvalidWinningTicket)?;
+
+    require_keys_eq!(ticket.key(), expected, ErrorCode::InvalidWinningTicket);
+    require!(
+        ticket.raffle == raffle_key && ticket.entry_index == entry_index,
+        ErrorCode::InvalidWinningTicket
+    );
+
+    Ok(ticket.buyer)
+}
+
+/// Returns the first entry after `skipped` (wrapping) that can replace it
+///
+/// Used by `allow_fallback` and `require_gate_at_draw` draws: the entry's
+/// buyer must not already be `taken` by another winner and must be
+/// `eligible` to win. Only the raffle's `eligible_entries` are considered,
+/// so a replacement is never the entry `exclude_last_buyer` leaves out.
+pub fn fallback_index(
+    raffle: &Raffle,
+    skipped: usize,
+    taken: &[Pubkey],
+    eligible: impl Fn(&Pubkey) -> Result<bool>,
+) -> Result<usize> {
+    let entries = raffle.eligible_entries()?;
+
+    for offset in 1..entries {
+        let index = (skipped + offset) % entries;
+        let buyer = raffle.ticket_buyers[index];
+        if !taken.contains(&buyer) && eligible(&buyer)? {
+            return Ok(index);
+        }
+    }
+
+    err!(ErrorCode::InvalidWinningTicket)
+}
+
+/// Returns whether `owner` still holds `gate_mint`
+///
+/// Reads `owner`'s associated token account for `gate_mint`, which must be
+/// in `remaining_accounts` so a winner cannot be skipped by leaving it out.
+/// An associated token account that does not exist holds nothing.
+pub fn holds_gate<'info>(
+    remaining_accounts: &'info [AccountInfo<'info>],
+    owner: &Pubkey,
+    gate_mint: &Pubkey,
+) -> Result<bool> {
+    let address = get_associated_token_address(owner, gate_mint);
+    let account = remaining_accounts
+        .iter()
+        .find(|acc| acc.key() == address)
+        .ok_or(ErrorCode::GateAccountMissing)?;
+
+    if account.data_is_empty() {
+        return Ok(false);
+    }
+
+    let token_account = Account::<TokenAccount>::try_from(account)?;
+    Ok(token_account.amount > 0)
+}
+
+/// Returns who currently owns the entry at `index`
+///
+/// Entries whose ticket was minted as an NFT belong to the NFT holder; the
+/// ticket, the holder's token account and the holder's wallet are looked up
+/// in `remaining_accounts`. Other entries belong to the original buyer.
+pub fn resolve_entry_owner<'info>(
+    raffle: &Raffle,
+    raffle_key: Pubkey,
+    index: usize,
+    remaining_accounts: &'info [AccountInfo<'info>],
+) -> Result<Pubkey> {
+    let buyer = *raffle
+        .ticket_buyers
+        .get(index)
+        .ok_or(ErrorCode::InvalidWinningTicket)?;
+
+    if raffle.tokenized_entries == 0 {
+        return Ok(buyer);
+    }
+
+    // Locate the entry's ticket (owner and discriminator checked by Anchor)
+    let ticket = remaining_accounts
+        .iter()
+        .filter_map(|acc| Account::<Ticket>::try_from(acc).ok())
+        .find(|ticket| ticket.raffle == raffle_key && ticket.entry_index as usize == index)
+        .ok_or(ErrorCode::InvalidWinningTicket)?;
+
+    let nft_mint = match ticket.nft_mint {
+        Some(nft_mint) => nft_mint,
+        None => return Ok(buyer),
+    };
+
+    let holder_account = remaining_accounts
+        .iter()
+        .filter_map(|acc| Account::<TokenAccount>::try_from(acc).ok())
+        .find(|token_account| token_account.mint == nft_mint && token_account.amount == 1)
+        .ok_or(ErrorCode::InvalidTicketNft)?;
+
+    Ok(holder_account.owner)
+}
+
+/// Derives the draw seed from the raffle's randomness source
+///
+/// `SlotBased` uses the draw slot. `SlotHashes` uses the most recent slot
+/// hash, read from the sysvar in `remaining_accounts`. `CommitReveal`
+/// hashes the revealed creator secret with the draw slot. `Beacon` hashes
+/// the published beacon value with the raffle's identity.
+pub fn draw_seed(raffle: &Raffle, slot: u64, remaining: &[AccountInfo]) -> Result<u64> {
+    match raffle.randomness_source {
+        RandomnessSource::SlotBased => Ok(slot),
+        RandomnessSource::SlotHashes => {
+            let slot_hashes = remaining
+                .iter()
+                .find(|acc| acc.key() == anchor_lang::solana_program::sysvar::slot_hashes::ID)
+                .ok_or(ErrorCode::MissingRandomnessAccount)?;
+
+            // Layout: u64 entry count, then (u64 slot, [u8; 32] hash) entries
+            let data = slot_hashes.try_borrow_data()?;
+            require!(data.len() >= 48, ErrorCode::MissingRandomnessAccount);
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&data[16..24]);
+            Ok(u64::from_le_bytes(bytes))
+        }
+        RandomnessSource::CommitReveal => {
+            let secret = raffle.revealed_secret.ok_or(ErrorCode::RandomnessNotRevealed)?;
+            let hash = hashv(&[&secret, &slot.to_le_bytes()]);
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&hash.to_bytes()[..8]);
+            Ok(u64::from_le_bytes(bytes))
+        }
+        RandomnessSource::Vrf => err!(ErrorCode::UnsupportedRandomnessSource),
+        RandomnessSource::Beacon => {
+            let value = raffle.beacon_value.ok_or(ErrorCode::BeaconNotPublished)?;
+            let hash = hashv(&[&value, raffle.creator.as_ref(), &raffle.raffle_id.to_le_bytes()]);
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&hash.to_bytes()[..8]);
+            Ok(u64::from_le_bytes(bytes))
+        }
+    }
+}
+
+/// Derives the seed for the `n`-th winner of a draw
+///
+/// The first winner uses the raw seed so single-winner draws are unchanged.
+pub fn winner_seed(seed: u64, n: u64) -> u64 {
+    if n == 0 {
+        return seed;
+    }
+    let hash = hashv(&[&seed.to_le_bytes(), &n.to_le_bytes()]);
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash.to_bytes()[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// Selects `winners_count` entries owned by distinct buyers
+///
+/// Each winner starts from `select_winning_index` with its own derived seed.
+/// Tie-break: if that entry's buyer has already won, the index advances by a
+/// hashed step that is coprime with the entry count, so the probe visits
+/// every entry before repeating. Selection therefore always terminates when
+/// `winners_count` does not exceed the number of unique buyers.
+pub fn select_winning_indices(raffle: &Raffle, seed: u64) -> Result<Vec<usize>> {
+    let entries = raffle.ticket_buyers.len();
+    require!(entries > 0, ErrorCode::NoTicketsSold);
+    require!(
+        raffle.winners_count as usize <= raffle.unique_buyers(),
+        ErrorCode::NotEnoughParticipants
+    );
+
+    let mut indices: Vec<usize> = Vec::with_capacity(raffle.winners_count as usize);
+    for n in 0..raffle.winners_count as u64 {
+        let index = select_distinct_index(raffle, winner_seed(seed, n), &indices)?;
+        indices.push(index);
+    }
+
+    Ok(indices)
+}
+
+/// Selects an entry whose buyer owns none of the `taken` entries
+///
+/// Starts from `select_winning_index` and applies the tie-break described
+/// on `select_winning_indices`.
+pub fn select_distinct_index(raffle: &Raffle, seed: u64, taken: &[usize]) -> Result<usize> {
+    let entries = raffle.eligible_entries()?;
+    require!(entries > 0, ErrorCode::NoTicketsSold);
+
+    let is_taken = |index: usize| {
+        taken
+            .iter()
+            .any(|&taken| raffle.ticket_buyers[taken] == raffle.ticket_buyers[index])
+    };
+
+    let mut index = select_winning_index(raffle, seed)?;
+    if is_taken(index) {
+        let mut step = (seed.rotate_left(32) % entries as u64) as usize;
+        while step == 0 || gcd(step, entries) != 1 {
+            step += 1;
+        }
+
+        let mut probes = 0;
+        while is_taken(index) {
+            require!(probes < entries, ErrorCode::NotEnoughParticipants);
+            index = (index + step) % entries;
+            probes += 1;
+        }
+    }
+
+    Ok(index)
+}
+
+fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Maps a random seed to the index of the winning entry in `ticket_buyers`
+///
+/// Only the raffle's eligible entries can be selected (see
+/// `Raffle::eligible_entries`).
+pub fn select_winning_index(raffle: &Raffle, seed: u64) -> Result<usize> {
+    let eligible = raffle.eligible_entries()?;
+
+    match raffle.selection_mode {
+        SelectionMode::PerTicket => {
+            // A zero entry count yields an error instead of a panic
+            let index = seed
+                .checked_rem(eligible as u64)
+                .ok_or(ErrorCode::NoTicketsSold)?;
+            Ok(index as usize)
+        }
+        SelectionMode::PerLamport => {
+            // Pick a point in [0, eligible revenue) and find the entry whose
+            // cumulative contribution range contains it
+            let eligible_revenue = eligible
+                .checked_sub(1)
+                .and_then(|last| raffle.entry_cumulative.get(last))
+                .copied()
+                .unwrap_or(0);
+            let point = seed
+                .checked_rem(eligible_revenue)
+                .ok_or(ErrorCode::NoTicketsSold)?;
+            let index = raffle.entry_cumulative[..eligible]
+                .partition_point(|&cumulative| cumulative <= point);
+            require!(index < eligible, ErrorCode::InvalidWinningTicket);
+            Ok(index)
+        }
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + PlatformConfig::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, PlatformConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, PlatformConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTvlTracker<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + TvlTracker::INIT_SPACE,
+        seeds = [b"tvl"],
+        bump
+    )]
+    pub tvl_tracker: Account<'info, TvlTracker>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, PlatformConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReadTvl<'info> {
+    #[account(seeds = [b"tvl"], bump = tvl_tracker.bump)]
+    pub tvl_tracker: Account<'info, TvlTracker>,
+}
+
+#[derive(Accounts)]
+pub struct JoinLeaderboard<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + LeaderboardEntry::INIT_SPACE,
+        seeds = [b"leaderboard", creator.key().as_ref()],
+        bump
+    )]
+    pub leaderboard: Account<'info, LeaderboardEntry>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReadLeaderboard<'info> {
+    #[account(
+        seeds = [b"leaderboard", leaderboard.creator.as_ref()],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Account<'info, LeaderboardEntry>,
+}
+
+#[derive(Accounts)]
+#[instruction(state: RaffleState)]
+pub struct InitializeRaffleIndex<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + RaffleIndex::INIT_SPACE,
+        seeds = [b"raffle_index".as_ref(), &[state.clone() as u8]],
+        bump
+    )]
+    pub raffle_index: Account<'info, RaffleIndex>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, PlatformConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetVerified<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, PlatformConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AdminFreeze<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, PlatformConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(raffle_id: u64)]
+pub struct CreateRaffle<'info> {
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + Raffle::INIT_SPACE,
+        seeds = [b"raffle", creator.key().as_ref(), raffle_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, PlatformConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + CreatorState::INIT_SPACE,
+        seeds = [b"creator", creator.key().as_ref()],
+        bump
+    )]
+    pub creator_state: Account<'info, CreatorState>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Index of raffles entering the new state (optional)
+    #[account(mut)]
+    pub raffle_index: Option<Account<'info, RaffleIndex>>,
+
+    /// Parent raffle named in `options.parent` (optional)
+    #[account(mut)]
+    pub parent_raffle: Option<Account<'info, Raffle>>,
+
+    /// Creator's treasury, required with `options.use_treasury`
+    #[account(seeds = [b"treasury", creator.key().as_ref()], bump = treasury.bump)]
+    pub treasury: Option<Account<'info, Treasury>>,
+}
+
+#[derive(Accounts)]
+#[instruction(on_behalf_of: Option<Pubkey>)]
+pub struct BuyTicket<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + Ticket::INIT_SPACE,
+        seeds = [b"ticket", raffle.key().as_ref(), raffle.total_tickets_sold.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub ticket: Account<'info, Ticket>,
+
+    /// Purchase record of the entrant (the gift recipient, if any)
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + BuyerRecord::INIT_SPACE,
+        seeds = [
+            b"buyer_record",
+            raffle.key().as_ref(),
+            on_behalf_of.unwrap_or(buyer.key()).as_ref()
+        ],
+        bump
+    )]
+    pub buyer_record: Account<'info, BuyerRecord>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// Buyer's token account for the raffle's discount mint (optional)
+    pub discount_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Attestor co-signing snapshot eligibility (required for gated raffles)
+    pub snapshot_attestor: Option<Signer<'info>>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Platform TVL, updated with the revenue this instruction moves
+    #[account(mut, seeds = [b"tvl"], bump = tvl_tracker.bump)]
+    pub tvl_tracker: Account<'info, TvlTracker>,
+}
+
+#[derive(Accounts)]
+pub struct BuyTicketRelayed<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + Ticket::INIT_SPACE,
+        seeds = [b"ticket", raffle.key().as_ref(), raffle.total_tickets_sold.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub ticket: Account<'info, Ticket>,
+
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        space = 8 + BuyerRecord::INIT_SPACE,
+        seeds = [b"buyer_record", raffle.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub buyer_record: Account<'info, BuyerRecord>,
+
+    /// Buyer paying the ticket price
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// Relayer paying transaction fees and account rent
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    /// Buyer's token account for the raffle's discount mint (optional)
+    pub discount_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Attestor co-signing snapshot eligibility (required for gated raffles)
+    pub snapshot_attestor: Option<Signer<'info>>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Platform TVL, updated with the revenue this instruction moves
+    #[account(mut, seeds = [b"tvl"], bump = tvl_tracker.bump)]
+    pub tvl_tracker: Account<'info, TvlTracker>,
+}
+
+#[derive(Accounts)]
+pub struct BuyTickets<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + BuyerRecord::INIT_SPACE,
+        seeds = [b"buyer_record", raffle.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub buyer_record: Account<'info, BuyerRecord>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// Buyer's token account for the raffle's discount mint (optional)
+    pub discount_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Attestor co-signing snapshot eligibility (required for gated raffles)
+    pub snapshot_attestor: Option<Signer<'info>>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Platform TVL, updated with the revenue this instruction moves
+    #[account(mut, seeds = [b"tvl"], bump = tvl_tracker.bump)]
+    pub tvl_tracker: Account<'info, TvlTracker>,
+}
+
+#[derive(Accounts)]
+pub struct DrawWinner<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    /// CHECK: Account to receive the fee, must match the raffle's fee recipient
+    #[account(mut, address = raffle.fee_recipient @ ErrorCode::Unauthorized)]
+    pub creator: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"creator", raffle.creator.as_ref()],
+        bump = creator_state.bump
+    )]
+    pub creator_state: Account<'info, CreatorState>,
+
+    /// Anyone triggering the draw, receives the keeper reward
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Platform TVL, updated with the revenue this instruction moves
+    #[account(mut, seeds = [b"tvl"], bump = tvl_tracker.bump)]
+    pub tvl_tracker: Account<'info, TvlTracker>,
+
+    /// Index of raffles entering the new state (optional)
+    #[account(mut)]
+    pub raffle_index: Option<Account<'info, RaffleIndex>>,
+
+    /// Creator's leaderboard entry, when they joined the leaderboard (optional)
+    #[account(mut)]
+    pub leaderboard: Option<Account<'info, LeaderboardEntry>>,
+
+    /// Ticket PDA of the winning entry, naming a single winner (optional)
+    pub winning_ticket: Option<Account<'info, Ticket>>,
+
+    /// CHECK: Winner receiving the prize, must match the resolved winner (optional)
+    #[account(mut)]
+    pub winner: Option<UncheckedAccount<'info>>,
+    // Without them, the winner account is found in remaining_accounts
+
+    /// CHECK: Creator account receiving the fixed prize back from a no
+    /// contest, must match the raffle creator (optional otherwise)
+    #[account(mut, address = raffle.creator @ ErrorCode::Unauthorized)]
+    pub raffle_creator: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct DrawNextPrize<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        mut,
+        seeds = [b"creator", raffle.creator.as_ref()],
+        bump = creator_state.bump
+    )]
+    pub creator_state: Account<'info, CreatorState>,
+
+    /// Anyone triggering the prize draw
+    pub keeper: Signer<'info>,
+
+    /// Index of raffles entering the new state (optional)
+    #[account(mut)]
+    pub raffle_index: Option<Account<'info, RaffleIndex>>,
+
+    /// Platform TVL, updated with the revenue this instruction moves
+    #[account(mut, seeds = [b"tvl"], bump = tvl_tracker.bump)]
+    pub tvl_tracker: Account<'info, TvlTracker>,
+
+    /// Creator's leaderboard entry, when they joined the leaderboard (optional)
+    #[account(mut)]
+    pub leaderboard: Option<Account<'info, LeaderboardEntry>>,
+}
+
+#[derive(Accounts)]
+pub struct RecordDrawAttempt<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        init,
+        payer = caller,
+        space = 8 + DrawAttempt::INIT_SPACE,
+        seeds = [b"draw_attempt", raffle.key().as_ref(), raffle.draw_attempts.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub draw_attempt: Account<'info, DrawAttempt>,
+
+    #[account(mut)]
+    pub caller: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeResult<'info> {
+    #[account(
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ResultCertificate::INIT_SPACE,
+        seeds = [b"certificate", raffle.key().as_ref()],
+        bump
+    )]
+    pub certificate: Account<'info, ResultCertificate>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterInterest<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + InterestRecord::INIT_SPACE,
+        seeds = [b"interest", raffle.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub interest_record: Account<'info, InterestRecord>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenSales<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    /// Index of active raffles to record the opening in, if tracked
+    #[account(mut)]
+    pub raffle_index: Option<Account<'info, RaffleIndex>>,
+}
+
+#[derive(Accounts)]
+pub struct EndRaffle<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+}
+
+#[derive(Accounts)]
+pub struct SetSplits<'info> {
+    #[account(
+        mut,
+        has_one = creator @ ErrorCode::Unauthorized,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeeRecipient<'info> {
+    #[account(
+        mut,
+        has_one = creator @ ErrorCode::Unauthorized,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GrowRaffle<'info> {
+    #[account(
+        mut,
+        has_one = creator @ ErrorCode::Unauthorized,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConfirmContinue<'info> {
+    #[account(
+        mut,
+        has_one = creator @ ErrorCode::Unauthorized,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PublishBeacon<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    pub beacon_signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RevealRandomness<'info> {
+    #[account(
+        mut,
+        has_one = creator @ ErrorCode::Unauthorized,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReadRaffle<'info> {
+    #[account(
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+}
+
+#[derive(Accounts)]
+pub struct ReadCurrentPrice<'info> {
+    #[account(
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    /// Buyer's token account for the raffle's discount mint (optional)
+    pub discount_token_account: Option<Account<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct ReadBuyerRecord<'info> {
+    #[account(
+        seeds = [b"buyer_record", buyer_record.raffle.as_ref(), buyer_record.buyer.as_ref()],
+        bump = buyer_record.bump
+    )]
+    pub buyer_record: Account<'info, BuyerRecord>,
+}
+
+#[derive(Accounts)]
+pub struct ReadEntryTiming<'info> {
+    #[account(
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        seeds = [b"buyer_record", raffle.key().as_ref(), buyer_record.buyer.as_ref()],
+        bump = buyer_record.bump
+    )]
+    pub buyer_record: Account<'info, BuyerRecord>,
+}
+
+#[derive(Accounts)]
+pub struct ReadTicket<'info> {
+    #[account(
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    pub ticket: Account<'info, Ticket>,
+}
+
+#[derive(Accounts)]
+#[instruction(buyer: Pubkey)]
+pub struct ReadBuyerRemaining<'info> {
+    #[account(
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    /// Buyer's purchase record, if they have entered
+    #[account(
+        seeds = [b"buyer_record", raffle.key().as_ref(), buyer.as_ref()],
+        bump = buyer_record.bump
+    )]
+    pub buyer_record: Option<Account<'info, BuyerRecord>>,
+}
+
+#[derive(Accounts)]
+pub struct ReadCreatorState<'info> {
+    #[account(
+        seeds = [b"creator", creator_state.creator.as_ref()],
+        bump = creator_state.bump
+    )]
+    pub creator_state: Account<'info, CreatorState>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Treasury::INIT_SPACE,
+        seeds = [b"treasury", creator.key().as_ref()],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(
+        mut,
+        has_one = creator @ ErrorCode::Unauthorized,
+        seeds = [b"treasury", creator.key().as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimFees<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReadCreatorRaffles<'info> {
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DeriveAddresses {}
+
+#[derive(Accounts)]
+pub struct MarkFailed<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        mut,
+        seeds = [b"creator", raffle.creator.as_ref()],
+        bump = creator_state.bump
+    )]
+    pub creator_state: Account<'info, CreatorState>,
+
+    /// Index of raffles entering the new state (optional)
+    #[account(mut)]
+    pub raffle_index: Option<Account<'info, RaffleIndex>>,
+
+    /// CHECK: Creator account receiving the fixed prize back, must match the raffle creator
+    #[account(mut, address = raffle.creator @ ErrorCode::Unauthorized)]
+    pub creator: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RefundTicket<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        mut,
+        close = buyer,
+        has_one = raffle @ ErrorCode::InvalidTicket,
+        seeds = [b"ticket", raffle.key().as_ref(), ticket.entry_index.to_le_bytes().as_ref()],
+        bump = ticket.bump
+    )]
+    pub ticket: Account<'info, Ticket>,
+
+    /// Ticket owner: the original buyer, or the NFT holder if tokenized
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// Holder's token account for the ticket NFT (tokenized tickets only)
+    pub holder_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Platform TVL, updated with the revenue this instruction moves
+    #[account(mut, seeds = [b"tvl"], bump = tvl_tracker.bump)]
+    pub tvl_tracker: Account<'info, TvlTracker>,
+}
+
+#[derive(Accounts)]
+pub struct MintTicketNft<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        mut,
+        has_one = raffle @ ErrorCode::InvalidTicket,
+        has_one = buyer @ ErrorCode::Unauthorized,
+        seeds = [b"ticket", raffle.key().as_ref(), ticket.entry_index.to_le_bytes().as_ref()],
+        bump = ticket.bump
+    )]
+    pub ticket: Account<'info, Ticket>,
+
+    #[account(
+        init,
+        payer = buyer,
+        mint::decimals = 0,
+        mint::authority = raffle,
+        mint::freeze_authority = raffle,
+        seeds = [b"ticket_mint", ticket.key().as_ref()],
+        bump
+    )]
+    pub ticket_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = buyer,
+        associated_token::mint = ticket_mint,
+        associated_token::authority = buyer
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Metaplex metadata of the ticket mint, created by the CPI
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), ticket_mint.key().as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ConfirmDelivery<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    pub winner: Signer<'info>,
+
+    /// CHECK: Creator account receiving the bond, must match the raffle creator
+    #[account(mut, address = raffle.creator @ ErrorCode::Unauthorized)]
+    pub creator: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RevealClaimCode<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    pub winner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPrize<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(mut)]
+    pub winner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimConsolation<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        mut,
+        seeds = [b"buyer_record", raffle.key().as_ref(), buyer.key().as_ref()],
+        bump = buyer_record.bump
+    )]
+    pub buyer_record: Account<'info, BuyerRecord>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimClawbackRefund<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        mut,
+        seeds = [b"buyer_record", raffle.key().as_ref(), buyer.key().as_ref()],
+        bump = buyer_record.bump
+    )]
+    pub buyer_record: Account<'info, BuyerRecord>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AdvanceWinner<'info> {
+    #[account(
+        mut,
+        constraint = child.parent == Some(parent.key()) @ ErrorCode::InvalidParent,
+        seeds = [b"raffle", child.creator.as_ref(), child.raffle_id.to_le_bytes().as_ref()],
+        bump = child.bump
+    )]
+    pub child: Account<'info, Raffle>,
+
+    #[account(
+        mut,
+        seeds = [b"raffle", parent.creator.as_ref(), parent.raffle_id.to_le_bytes().as_ref()],
+        bump = parent.bump
+    )]
+    pub parent: Account<'info, Raffle>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Ticket::INIT_SPACE,
+        seeds = [b"ticket", parent.key().as_ref(), parent.total_tickets_sold.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub ticket: Account<'info, Ticket>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + BuyerRecord::INIT_SPACE,
+        seeds = [b"buyer_record", parent.key().as_ref(), child.winner.unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub buyer_record: Account<'info, BuyerRecord>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SlashBond<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    /// CHECK: Winner receiving the slashed bond, checked against the raffle winner
+    #[account(mut)]
+    pub winner: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRaffle<'info> {
+    #[account(
+        mut,
+        close = creator,
+        has_one = creator @ ErrorCode::Unauthorized,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// Parent of a child raffle, required when `raffle.parent` is set
+    #[account(mut)]
+    pub parent_raffle: Option<Account<'info, Raffle>>,
+}
+
+#[derive(Accounts)]
+pub struct MergeRaffles<'info> {
+    #[account(
+        mut,
+        close = creator,
+        has_one = creator @ ErrorCode::Unauthorized,
+        seeds = [b"raffle", source.creator.as_ref(), source.raffle_id.to_le_bytes().as_ref()],
+        bump = source.bump
+    )]
+    pub source: Account<'info, Raffle>,
+
+    #[account(
+        mut,
+        has_one = creator @ ErrorCode::Unauthorized,
+        seeds = [b"raffle", target.creator.as_ref(), target.raffle_id.to_le_bytes().as_ref()],
+        bump = target.bump
+    )]
+    pub target: Account<'info, Raffle>,
+
+    #[account(
+        mut,
+        seeds = [b"creator", creator.key().as_ref()],
+        bump = creator_state.bump
+    )]
+    pub creator_state: Account<'info, CreatorState>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeEmpty<'info> {
+    #[account(
+        mut,
+        close = creator,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    /// CHECK: Creator account receiving the rent, must match the raffle creator
+    #[account(mut, address = raffle.creator @ ErrorCode::Unauthorized)]
+    pub creator: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"creator", raffle.creator.as_ref()],
+        bump = creator_state.bump
+    )]
+    pub creator_state: Account<'info, CreatorState>,
+
+    /// Parent of a child raffle, required when `raffle.parent` is set
+    #[account(mut)]
+    pub parent_raffle: Option<Account<'info, Raffle>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelRaffle<'info> {
+    #[account(
+        mut,
+        close = creator,
+        has_one = creator @ ErrorCode::Unauthorized,
+        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        mut,
+        seeds = [b"creator", creator.key().as_ref()],
+        bump = creator_state.bump
+    )]
+    pub creator_state: Account<'info, CreatorState>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// Platform TVL, updated with the revenue this instruction moves
+    #[account(mut, seeds = [b"tvl"], bump = tvl_tracker.bump)]
+    pub tvl_tracker: Account<'info, TvlTracker>,
+
+    /// Parent of a child raffle, required when `raffle.parent` is set
+    #[account(mut)]
+    pub parent_raffle: Option<Account<'info, Raffle>>,
+}
+
+// ============================================================================
+// Account Data Structures
+// ============================================================================
+
+#[account]
+#[derive(InitSpace)]
+pub struct Raffle {
+    /// Creator of the raffle
+    pub creator: Pubkey,
+
+    /// Price per ticket in lamports
+    pub ticket_price: u64,
+
+    /// Maximum number of tickets
+    pub max_tickets: u32,
+
+    /// Unix timestamp when raffle ends
+    pub end_time: i64,
+
+    /// Current number of tickets sold
+    pub total_tickets_sold: u32,
+
+    /// List of all ticket buyers (stores buyer pubkey for each ticket)
+    #[max_len(MAX_ENTRIES)]
+    pub ticket_buyers: Vec<Pubkey>,
+
+    /// Winner's public key (None until drawn)
+    pub winner: Option<Pubkey>,
+
+    /// Current state of the raffle
+    pub state: RaffleState,
+
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Unique raffle identifier
+    pub raffle_id: u64,
+
+    /// How winning odds are weighted across entries
+    pub selection_mode: SelectionMode,
+
+    /// Total lamports collected from ticket sales
+    pub total_revenue: u64,
+
+    /// Running lamport total after each entry (parallel to `ticket_buyers`)
+    #[max_len(MAX_ENTRIES)]
+    pub entry_cumulative: Vec<u64>,
+
+    /// Minimum tickets required for the raffle to be drawn (0 = no minimum)
+    pub min_tickets: u32,
+
+    /// Number of tickets refunded after the raffle failed
+    pub tickets_refunded: u32,
+
+    /// Monotonic counter assigned to each purchase in order
+    pub purchase_sequence: u64,
+
+    /// Optional charity receiving a share of the pot
+    pub charity: Option<Pubkey>,
+
+    /// Charity share of the pot in basis points
+    pub charity_bps: u16,
+
+    /// Mint whose holders pay a discounted ticket price
+    pub discount_mint: Option<Pubkey>,
+
+    /// Holder discount in basis points of `ticket_price`
+    pub discount_bps: u16,
+
+    /// Lamports paid from the pot to whoever triggers the draw
+    pub keeper_reward: u64,
+
+    /// Slot in which the raffle entered the Ended state
+    pub ended_at_slot: u64,
+
+    /// Number of tickets converted to NFT receipts
+    pub tokenized_entries: u32,
+
+    /// Number of distinct winners drawn
+    pub winners_count: u8,
+
+    /// All winners in draw order (`winner` holds the first)
+    #[max_len(10)]
+    pub winners: Vec<Pubkey>,
+
+    /// Revenue at which sales pause for creator review (0 = disabled)
+    pub soft_cap: u64,
+
+    /// Whether the creator already chose to continue past the soft cap
+    pub soft_cap_cleared: bool,
+
+    /// Where the draw seed comes from
+    pub randomness_source: RandomnessSource,
+
+    /// Hash of the creator's secret for commit-reveal raffles
+    pub commitment: Option<[u8; 32]>,
+
+    /// Creator's secret once revealed
+    pub revealed_secret: Option<[u8; 32]>,
+
+    /// Number of draw attempts recorded (see `DrawAttempt`)
+    pub draw_attempts: u32,
+
+    /// Maximum lamports the raffle may collect (0 = no cap)
+    pub max_total_revenue: u64,
+
+    /// Whether sales end once no full-price ticket fits under the cap
+    pub end_at_revenue_cap: bool,
+
+    /// Seed the draw selected winners from
+    pub draw_seed: u64,
+
+    /// Slot the draw ran in
+    pub draw_slot: u64,
+
+    /// Entry index of each winner, in draw order
+    #[max_len(10)]
+    pub winning_indices: Vec<u32>,
+
+    /// Whether each wallet may hold at most one ticket
+    pub one_per_wallet: bool,
+
+    /// Minimum ticket revenue required for the raffle to be drawn (0 = no minimum)
+    pub min_pot: u64,
+
+    /// Whether the creator fee stays in the raffle until `claim_fees`
+    pub escrow_fees: bool,
+
+    /// Escrowed creator fee not yet claimed
+    pub accrued_creator_fee: u64,
+
+    /// Slot at which entrants must have held the gating asset (0 = no snapshot)
+    pub snapshot_slot: u64,
+
+    /// Attestor that must co-sign purchases of a snapshot-gated raffle
+    pub snapshot_attestor: Option<Pubkey>,
+
+    /// Added to each entry index to form the displayed ticket number
+    pub ticket_number_offset: u32,
+
+    /// Separate prizes drawn from the shared entry pool
+    #[max_len(MAX_PRIZES)]
+    pub prizes: Vec<PrizeSpec>,
+
+    /// Number of prizes (or batched winners) drawn so far
+    pub drawn_count: u8,
+
+    /// Whether one buyer may win several prizes
+    pub allow_multiple_wins: bool,
+
+    /// Entry index of each prize winner, in prize order
+    #[max_len(MAX_PRIZES)]
+    pub prize_entry_indices: Vec<u32>,
+
+    /// Winner of each prize, in prize order
+    #[max_len(MAX_PRIZES)]
+    pub prize_winners: Vec<Pubkey>,
+
+    /// Lamports the creator posted as a delivery bond
+    pub bond: u64,
+
+    /// Whether the bond has been paid out, by release or by slashing
+    pub bond_released: bool,
+
+    /// Unix timestamp of the draw completing (0 until drawn)
+    pub drawn_at: i64,
+
+    /// Key the raffle was created with, recognizing retried creations
+    pub idempotency_key: Option<[u8; 16]>,
+
+    /// Total prize shared by the winners of a batched draw, fixed at the first batch
+    pub batch_winner_prize: u64,
+
+    /// Whether the platform admin has verified this raffle
+    pub verified: bool,
+
+    /// Price reached at `end_time` on a linear price curve (0 = fixed price)
+    pub end_price: u64,
+
+    /// Unix timestamp the price curve starts from (creation time)
+    pub price_start_time: i64,
+
+    /// Whether draws leave out the most recent entry
+    pub exclude_last_buyer: bool,
+
+    /// Whether the creator may draw before `end_time`
+    pub allow_early_draw: bool,
+
+    /// Upper bound on the keeper tip (0 = uncapped)
+    pub max_keeper_tip: u64,
+
+    /// Whether the winner's prize stays in the raffle until `claim_prize`
+    pub escrow_prize: bool,
+
+    /// Escrowed prize owed to the winner
+    pub prize_owed: u64,
+
+    /// Part of the escrowed prize the winner has already claimed
+    pub claimed_so_far: u64,
+
+    /// Why the raffle ended without a winner (None = still running or drawn)
+    pub termination_reason: Option<TerminationReason>,
+
+    /// Whether `ticket_buyers` is frozen for the draw (set on entering `Ended`)
+    pub buyers_frozen: bool,
+
+    /// Number of entries the account has room for (see `grow_raffle`)
+    pub entry_capacity: u32,
+
+    /// Share of the pot split between non-winners, in basis points
+    pub consolation_bps: u16,
+
+    /// Lamports set aside at the draw for `claim_consolation`
+    pub consolation_pool: u64,
+
+    /// Entries held by non-winners, the pool's divisor
+    pub consolation_entries: u32,
+
+    /// Entries whose consolation has been claimed
+    pub consolation_entries_claimed: u32,
+
+    /// Advertised collection of the prize NFT, fixed at creation and not verified
+    pub prize_collection: Option<Pubkey>,
+
+    /// Unix timestamp sales open at; the raffle is `Pending` until then
+    pub sale_start: i64,
+
+    /// Number of users who registered interest while `Pending`
+    pub interest_count: u32,
+
+    /// Creator-funded prize held apart from ticket revenue
+    pub fixed_prize: u64,
+
+    /// Whether the fixed prize has been paid out or returned to the creator
+    pub fixed_prize_settled: bool,
+
+    /// Whether draws reduce `winners_count` to the number of distinct buyers
+    pub shrink_winners: bool,
+
+    /// Beacon round whose randomness decides a `Beacon` draw
+    pub beacon_round: u64,
+
+    /// Key allowed to publish the beacon round's value
+    pub beacon_signer: Option<Pubkey>,
+
+    /// Beacon value once published
+    pub beacon_value: Option<[u8; 32]>,
+
+    /// Number of tranches an escrowed prize vests in (0 = no vesting)
+    pub vesting_tranches: u8,
+
+    /// Seconds between vesting tranches, starting from the draw
+    pub vesting_interval: i64,
+
+    /// Account paid the creator fee at draw time, the creator or their treasury
+    /// unless reassigned
+    pub fee_recipient: Pubkey,
+
+    /// Parent raffle this child feeds its winner and `parent_share_bps` into
+    pub parent: Option<Pubkey>,
+
+    /// Share of the pot sent to the parent's pot at the draw, in basis points
+    pub parent_share_bps: u16,
+
+    /// Whether `advance_winner` entered the winner into the parent
+    pub winner_advanced: bool,
+
+    /// Whether selling out makes the raffle drawable before `end_time`
+    pub auto_draw_on_sellout: bool,
+
+    /// Whether the platform admin froze purchases and draws
+    pub frozen: bool,
+
+    /// Total prize paid or owed to the winners at the draw
+    pub winner_prize: u64,
+
+    /// Whether a missing winner account falls back to the next entry
+    pub allow_fallback: bool,
+
+    /// Winning entries skipped at the draw and the entries that replaced them
+    #[max_len(10)]
+    pub draw_fallbacks: Vec<DrawFallback>,
+
+    /// Seconds before `end_time` in which a purchase extends it (0 = off)
+    pub anti_snipe_window: i64,
+
+    /// Number of times late purchases extended `end_time`
+    pub end_time_extensions: u8,
+
+    /// Unix timestamp until which the creator may cancel despite sales
+    pub cooling_off_end: i64,
+
+    /// Mint winners must hold at draw time, with `require_gate_at_draw`
+    pub gate_mint: Option<Pubkey>,
+
+    /// Whether the draw skips winners no longer holding `gate_mint`
+    pub require_gate_at_draw: bool,
+
+    /// Hash of the off-chain code the creator hands the winner
+    pub claim_code: Option<[u8; 32]>,
+
+    /// Whether a winner revealed the claim code
+    pub claim_code_revealed: bool,
+
+    /// Minimum distinct buyers needed to draw (0 = no minimum)
+    pub min_unique_buyers: u32,
+
+    /// `DRAW_ALGO_VERSION` in effect when the raffle was created
+    pub algo_version: u8,
+
+    /// Creator fees clawed back by the admin for buyers to claim
+    pub clawback_pool: u64,
+
+    /// Entries whose buyers have claimed their clawback refund
+    pub clawback_entries_claimed: u32,
+
+    /// Only account allowed to draw, in place of the creator (`None` = any keeper)
+    pub draw_authority: Option<Pubkey>,
+
+    /// Child raffles naming this raffle as their parent that are not closed yet
+    pub child_count: u32,
+}
+
+impl Raffle {
+    /// Returns the account size needed to store `capacity` entries
+    pub fn space_for(capacity: u32) -> usize {
+        let extra = (capacity as usize).saturating_sub(MAX_ENTRIES);
+        8 + Raffle::INIT_SPACE + extra * ENTRY_SPACE
+    }
+
+    /// Returns the tip owed to the keeper triggering the draw
+    ///
+    /// The configured `keeper_reward`, capped at `max_keeper_tip` when set.
+    pub fn keeper_tip(&self) -> u64 {
+        if self.max_keeper_tip == 0 {
+            self.keeper_reward
+        } else {
+            self.keeper_reward.min(self.max_keeper_tip)
+        }
+    }
+
+    /// Returns how many leading entries can win
+    ///
+    /// With `exclude_last_buyer`, the most recent entry is left out so its
+    /// buyer cannot buy the last ticket and win the draw they trigger.
+    pub fn eligible_entries(&self) -> Result<usize> {
+        let entries = self.ticket_buyers.len();
+        if !self.exclude_last_buyer {
+            return Ok(entries);
+        }
+
+        require!(entries >= 2, ErrorCode::NotEnoughParticipants);
+        Ok(entries - 1)
+    }
+
+    /// Returns the number of entries bought by buyers who did not win
+    pub fn non_winning_entries(&self) -> u32 {
+        self.ticket_buyers
+            .iter()
+            .filter(|buyer| !self.winners.contains(buyer))
+            .count() as u32
+    }
+
+    /// Returns how much of the escrowed prize has vested at `now`
+    ///
+    /// Without vesting the whole prize is available at once. Otherwise one
+    /// of the `vesting_tranches` unlocks every `vesting_interval` seconds
+    /// after the draw.
+    pub fn vested_prize(&self, now: i64) -> Result<u64> {
+        if self.vesting_tranches == 0 {
+            return Ok(self.prize_owed);
+        }
+
+        let elapsed = now.saturating_sub(self.drawn_at).max(0);
+        let unlocked = (elapsed / self.vesting_interval).min(self.vesting_tranches as i64);
+
+        let vested = (self.prize_owed as u128)
+            .checked_mul(unlocked as u128)
+            .and_then(|product| product.checked_div(self.vesting_tranches as u128))
+            .and_then(|vested| u64::try_from(vested).ok())
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        Ok(vested)
+    }
+
+    /// Returns the escrowed prize the winner has yet to claim
+    pub fn unclaimed_prize(&self) -> u64 {
+        self.prize_owed.saturating_sub(self.claimed_so_far)
+    }
+
+    /// Returns the creator's fixed prize still held apart from ticket revenue
+    pub fn held_fixed_prize(&self) -> u64 {
+        if self.fixed_prize_settled {
+            0
+        } else {
+            self.fixed_prize
+        }
+    }
+
+    /// Returns the lamports held back from the distributable pot
+    pub fn reserved_lamports(&self) -> u64 {
+        self.held_bond().saturating_add(self.held_fixed_prize())
+    }
+
+    /// Returns the lamports still held as the creator's bond
+    pub fn held_bond(&self) -> u64 {
+        if self.bond_released {
+            0
+        } else {
+            self.bond
+        }
+    }
+
+    /// Reconciles `winners_count` with the number of distinct buyers
+    ///
+    /// With `shrink_winners` the draw awards only as many prizes as there
+    /// are buyers; otherwise too few buyers fail the draw.
+    pub fn fit_winners_to_participants(&mut self) -> Result<()> {
+        let unique_buyers = self.unique_buyers();
+        if self.winners_count as usize <= unique_buyers {
+            return Ok(());
+        }
+
+        require!(self.shrink_winners, ErrorCode::NotEnoughParticipants);
+        msg!(
+            "Raffle {} draws {} of {} winners (too few participants)",
+            self.raffle_id,
+            unique_buyers,
+            self.winners_count
+        );
+        self.winners_count = unique_buyers as u8;
+
+        Ok(())
+    }
+
+    /// Returns whether one buyer holds every entry, making the draw a no contest
+    ///
+    /// Entries converted to NFTs may have changed hands, so a raffle with
+    /// any is always contested.
+    pub fn is_no_contest(&self) -> bool {
+        self.unique_buyers() == 1 && self.tokenized_entries == 0
+    }
+
+    /// Returns the number of distinct buyers among all entries
+    pub fn unique_buyers(&self) -> usize {
+        self.ticket_buyers
+            .iter()
+            .enumerate()
+            .filter(|(i, buyer)| !self.ticket_buyers[..*i].contains(buyer))
+            .count()
+    }
+
+    /// Returns the lamports paid for the entry at `index`
+    ///
+    /// Exact per entry, as `entry_cumulative` records each payment at purchase.
+    pub fn entry_amount(&self, index: usize) -> Result<u64> {
+        let cumulative = *self
+            .entry_cumulative
+            .get(index)
+            .ok_or(ErrorCode::InvalidTicket)?;
+        let previous = match index {
+            0 => 0,
+            _ => self.entry_cumulative[index - 1],
+        };
+
+        cumulative
+            .checked_sub(previous)
+            .ok_or(ErrorCode::MathOverflow.into())
+    }
+
+    /// Moves the raffle to `Ended` at `slot` and freezes its entries
+    ///
+    /// Once frozen, `ticket_buyers` cannot change, so the draw selects from
+    /// exactly the entries that existed when sales closed.
+    pub fn mark_ended(&mut self, slot: u64) {
+        self.state = RaffleState::Ended;
+        self.ended_at_slot = slot;
+        self.buyers_frozen = true;
+    }
+
+    /// Ends an expired raffle and checks it can be drawn right now
+    ///
+    /// Shared by `draw_winner`, `draw_batch` and `draw_next_prize`. With a
+    /// `draw_authority` set, only it can draw, and it takes the creator's
+    /// place below. With `allow_early_draw`, a draw called by the creator
+    /// also ends the raffle before `end_time`. A sold-out raffle waits for
+    /// `end_time` unless created with `auto_draw_on_sellout`.
+    pub fn begin_draw(&mut self, clock: &Clock, caller: &Pubkey) -> Result<()> {
+        // Validation: Frozen raffles cannot be drawn
+        require!(!self.frozen, ErrorCode::RaffleFrozen);
+
+        // Validation: A raffle with a draw authority is drawn only by it
+        if let Some(authority) = self.draw_authority {
+            require_keys_eq!(*caller, authority, ErrorCode::NotDrawAuthority);
+        }
+        let by_creator = *caller == self.draw_authority.unwrap_or(self.creator);
+
+        // Validation: The raffle is drawn with the algorithm it was created for
+        require!(
+            self.algo_version == DRAW_ALGO_VERSION,
+            ErrorCode::AlgoVersionMismatch
+        );
+
+        let expired = clock.unix_timestamp >= self.end_time;
+        let early = self.allow_early_draw && by_creator;
+
+        // Check if raffle should end based on time or the creator's call
+        if self.state == RaffleState::Active && (expired || early) {
+            self.mark_ended(clock.slot);
+            if expired {
+                msg!("Raffle {} has ended (time expired)", self.raffle_id);
+            } else {
+                msg!("Raffle {} has ended (early draw by creator)", self.raffle_id);
+            }
+        } else {
+            // Validation: A raffle that ended earlier (e.g. the last ticket was
+            // bought) cannot be drawn in that same slot, which would let the
+            // buyer bundle buy + draw atomically
+            require!(
+                self.state != RaffleState::Ended || clock.slot > self.ended_at_slot,
+                ErrorCode::DrawSameSlot
+            );
+        }
+
+        // Validation: Check raffle is in Ended state
+        require!(
+            self.state == RaffleState::Ended,
+            ErrorCode::RaffleNotEnded
+        );
+
+        // Validation: The entries drawn from can no longer change
+        require!(self.buyers_frozen, ErrorCode::BuyersNotFrozen);
+
+        // Validation: Selling out only allows an immediate draw when opted in
+        require!(
+            self.auto_draw_on_sellout
+                || self.total_tickets_sold < self.max_tickets
+                || expired
+                || early,
+            ErrorCode::SelloutDrawNotAllowed
+        );
+
+        // Validation: Check tickets were sold
+        require!(
+            self.total_tickets_sold > 0,
+            ErrorCode::NoTicketsSold
+        );
+
+        // Validation: Every ticket sold has an entry to draw from
+        require!(
+            self.ticket_buyers.len() == self.total_tickets_sold as usize,
+            ErrorCode::EntryCountMismatch
+        );
+
+        // Validation: Under-subscribed raffles must go through mark_failed
+        require!(
+            self.total_tickets_sold >= self.min_tickets,
+            ErrorCode::MinTicketsNotMet
+        );
+
+        // Validation: Raffles below the minimum pot must go through mark_failed
+        require!(
+            self.total_revenue >= self.min_pot,
+            ErrorCode::PotBelowMinimum
+        );
+
+        // Validation: So must raffles with too few distinct buyers
+        require!(
+            self.unique_buyers() as u32 >= self.min_unique_buyers,
+            ErrorCode::TooFewBuyers
+        );
+
+        Ok(())
+    }
+
+    /// Returns how many more tickets an entrant holding `tickets_bought` may buy
+    pub fn wallet_allowance(&self, tickets_bought: u32) -> u32 {
+        if self.one_per_wallet {
+            if tickets_bought == 0 {
+                1
+            } else {
+                0
+            }
+        } else {
+            u32::MAX
+        }
+    }
+
+    /// Returns how many tickets at `price` still fit under the revenue cap
+    pub fn tickets_within_revenue_cap(&self, price: u64) -> u32 {
+        if self.max_total_revenue == 0 || price == 0 {
+            return u32::MAX;
+        }
+
+        let room = self.max_total_revenue.saturating_sub(self.total_revenue);
+        (room / price).min(u32::MAX as u64) as u32
+    }
+
+    /// Returns the undiscounted ticket price at `now`
+    ///
+    /// Fixed-price raffles charge `ticket_price`. With an `end_price`, the
+    /// price moves linearly from `ticket_price` at creation to `end_price`
+    /// at `end_time`.
+    pub fn base_price(&self, now: i64) -> u64 {
+        if self.end_price == 0 {
+            return self.ticket_price;
+        }
+
+        let duration = self.end_time.saturating_sub(self.price_start_time).max(1) as u128;
+        let elapsed = now.saturating_sub(self.price_start_time).clamp(0, duration as i64) as u128;
+
+        // The curve stays between both prices, so the result fits in a u64
+        if self.end_price < self.ticket_price {
+            let drop = (self.ticket_price - self.end_price) as u128 * elapsed / duration;
+            self.ticket_price - drop as u64
+        } else {
+            let rise = (self.end_price - self.ticket_price) as u128 * elapsed / duration;
+            self.ticket_price + rise as u64
+        }
+    }
+
+    /// Returns the lowest undiscounted price the raffle will charge
+    pub fn floor_price(&self) -> u64 {
+        if self.end_price == 0 {
+            self.ticket_price
+        } else {
+            self.ticket_price.min(self.end_price)
+        }
+    }
+
+    /// Ends the raffle when sold out or at the revenue cap, or pauses it
+    /// once revenue hits the soft cap
+    pub fn settle_sales_state(&mut self, slot: u64) {
+        if self.total_tickets_sold >= self.max_tickets {
+            self.mark_ended(slot);
+            msg!("Raffle {} has ended (sold out)", self.raffle_id);
+        } else if self.end_at_revenue_cap
+            && self.tickets_within_revenue_cap(self.floor_price()) == 0
+        {
+            self.mark_ended(slot);
+            msg!("Raffle {} has ended (revenue cap reached)", self.raffle_id);
+        } else if self.soft_cap > 0
+            && !self.soft_cap_cleared
+            && self.total_revenue >= self.soft_cap
+        {
+            self.state = RaffleState::PausedAtSoftCap;
+            msg!("Raffle {} paused at soft cap", self.raffle_id);
+        }
+    }
+
+    /// Extends `end_time` by `ANTI_SNIPE_EXTENSION` for a purchase made
+    /// within `anti_snipe_window` of it, at most `MAX_END_TIME_EXTENSIONS`
+    /// times. Returns the previous end time when it was extended.
+    pub fn extend_for_late_purchase(&mut self, now: i64) -> Result<Option<i64>> {
+        let late = self.end_time.saturating_sub(now) <= self.anti_snipe_window;
+        if self.anti_snipe_window == 0
+            || !late
+            || self.end_time_extensions >= MAX_END_TIME_EXTENSIONS
+        {
+            return Ok(None);
+        }
+
+        let old_end_time = self.end_time;
+        self.end_time = old_end_time
+            .checked_add(ANTI_SNIPE_EXTENSION)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.end_time_extensions += 1;
+        msg!("Raffle {} extended to {}", self.raffle_id, self.end_time);
+
+        Ok(Some(old_end_time))
+    }
+
+    /// Fails with the error matching `purchase_block`, if any
+    pub fn require_purchasable(&self, now: i64) -> Result<()> {
+        match self.purchase_block(now) {
+            PurchaseBlock::None => Ok(()),
+            PurchaseBlock::NotActive => err!(ErrorCode::RaffleNotActive),
+            PurchaseBlock::Paused => err!(ErrorCode::RafflePaused),
+            PurchaseBlock::SoldOut => err!(ErrorCode::RaffleSoldOut),
+            PurchaseBlock::Expired => err!(ErrorCode::RaffleExpired),
+            PurchaseBlock::NotOpen => err!(ErrorCode::SalesNotOpen),
+            PurchaseBlock::AdminFrozen => err!(ErrorCode::RaffleFrozen),
+        }
+    }
+
+    /// Returns why a purchase would be rejected right now, if at all
+    pub fn purchase_block(&self, now: i64) -> PurchaseBlock {
+        if self.frozen {
+            PurchaseBlock::AdminFrozen
+        } else if self.state == RaffleState::PausedAtSoftCap {
+            PurchaseBlock::Paused
+        } else if self.state == RaffleState::Pending {
+            PurchaseBlock::NotOpen
+        } else if self.state != RaffleState::Active {
+            PurchaseBlock::NotActive
+        } else if self.total_tickets_sold >= self.max_tickets {
+            PurchaseBlock::SoldOut
+        } else if now >= self.end_time {
+            PurchaseBlock::Expired
+        } else {
+            PurchaseBlock::None
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PlatformConfig {
+    /// Platform administrator allowed to update the config
+    pub admin: Pubkey,
+
+    /// Minimum seconds between two raffle creations by the same creator
+    pub min_create_interval: i64,
+
+    /// Maximum concurrent active raffles per creator (0 = unlimited)
+    pub max_active_raffles: u32,
+
+    /// Upper bound for a raffle's `max_tickets`
+    pub max_tickets_limit: u32,
+
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Minimum seconds between raffle creation and `end_time`
+    pub min_duration: i64,
+
+    /// SPL mints raffles may be paid in (empty = SOL only)
+    #[max_len(MAX_ALLOWED_MINTS)]
+    pub allowed_mints: Vec<Pubkey>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RaffleIndex {
+    /// State whose raffles this index records
+    pub state: RaffleState,
+
+    /// Most recent raffles to enter `state`, overwritten oldest-first once full
+    #[max_len(RAFFLE_INDEX_CAPACITY)]
+    pub raffles: Vec<Pubkey>,
+
+    /// Position the next raffle overwrites once `raffles` is full
+    pub cursor: u16,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl RaffleIndex {
+    /// Records `raffle` in the ring buffer
+    pub fn push(&mut self, raffle: Pubkey) {
+        if self.raffles.len() < RAFFLE_INDEX_CAPACITY {
+            self.raffles.push(raffle);
+        } else {
+            self.raffles[self.cursor as usize] = raffle;
+            self.cursor = ((self.cursor as usize + 1) % RAFFLE_INDEX_CAPACITY) as u16;
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct CreatorState {
+    /// Creator this state belongs to
+    pub creator: Pubkey,
+
+    /// Unix timestamp of the creator's most recent raffle creation
+    pub last_created_at: i64,
+
+    /// Number of the creator's raffles that have not completed yet
+    pub active_raffle_count: u32,
+
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Number of raffles the creator has ever created
+    pub created_count: u32,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Treasury {
+    /// Creator whose raffle fees collect here
+    pub creator: Pubkey,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TvlTracker {
+    /// Ticket revenue held by raffles not yet drawn, refunded or cancelled
+    pub total_locked: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl TvlTracker {
+    /// Adds a purchase's payment to the locked total
+    pub fn lock(&mut self, amount: u64) -> Result<()> {
+        self.total_locked = self
+            .total_locked
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Removes revenue settled by a draw, refund or cancellation
+    pub fn release(&mut self, amount: u64) -> Result<()> {
+        self.total_locked = self
+            .total_locked
+            .checked_sub(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct LeaderboardEntry {
+    /// Creator whose raffles this entry totals
+    pub creator: Pubkey,
+
+    /// Raffles completed with a winner since joining
+    pub total_raffles_completed: u32,
+
+    /// Ticket revenue of those raffles, in lamports
+    pub total_volume: u64,
+
+    /// Lamports paid or owed to their winners
+    pub total_prizes_paid: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Ticket {
+    /// Associated raffle public key
+    pub raffle: Pubkey,
+
+    /// Buyer/owner of the ticket
+    pub buyer: Pubkey,
+
+    /// Displayed ticket number (`entry_index` plus the raffle's offset)
+    pub ticket_number: u32,
+
+    /// Unix timestamp when ticket was purchased
+    pub purchase_time: i64,
+
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Position of this purchase in the raffle's purchase order
+    pub global_sequence: u64,
+
+    /// Mint of the NFT receipt, once the ticket has been tokenized
+    pub nft_mint: Option<Pubkey>,
+
+    /// Position in the raffle's entries (0-indexed), used for the PDA seeds
+    pub entry_index: u32,
+
+    /// Lamports paid for this entry, after the price curve and any discount
+    pub amount_paid: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct BuyerRecord {
+    /// Associated raffle public key
+    pub raffle: Pubkey,
+
+    /// Entrant whose purchases are tracked
+    pub buyer: Pubkey,
+
+    /// Number of tickets held by this entrant
+    pub tickets_bought: u32,
+
+    /// Purchase counter used as replay protection for relayed purchases
+    pub nonce: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Whether this entrant has claimed their consolation share
+    pub consolation_claimed: bool,
+
+    /// Lamports this entrant has paid for tickets
+    pub total_spent: u64,
+
+    /// Unix timestamp of this entrant's earliest purchase
+    pub first_purchase_time: i64,
+
+    /// Whether this entrant has claimed their clawback refund
+    pub clawback_claimed: bool,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct DrawAttempt {
+    /// Associated raffle public key
+    pub raffle: Pubkey,
+
+    /// Account that attempted the draw
+    pub caller: Pubkey,
+
+    /// Attempt index within the raffle (0-indexed)
+    pub attempt: u32,
+
+    /// Slot the attempt was made in
+    pub slot: u64,
+
+    /// Unix timestamp of the attempt
+    pub timestamp: i64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ResultCertificate {
+    /// Raffle whose outcome is certified
+    pub raffle: Pubkey,
+
+    /// Creator-assigned raffle id
+    pub raffle_id: u64,
+
+    /// Winners in draw order (empty for a no contest)
+    #[max_len(10)]
+    pub winners: Vec<Pubkey>,
+
+    /// Total prize paid or owed to the winners, in lamports
+    pub prize: u64,
+
+    /// Slot the draw ran in
+    pub draw_slot: u64,
+
+    /// Seed the winners were selected from, the draw's proof
+    pub draw_seed: u64,
+
+    /// Number of distinct buyers who entered
+    pub total_participants: u32,
+
+    /// Number of tickets sold
+    pub total_tickets: u32,
+
+    /// Unix timestamp the certificate was sealed at
+    pub sealed_at: i64,
+
+    /// Always true: certificates are immutable once written
+    pub sealed: bool,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct InterestRecord {
+    /// Associated raffle public key
+    pub raffle: Pubkey,
+
+    /// User who registered interest
+    pub user: Pubkey,
+
+    /// Unix timestamp of the registration
+    pub registered_at: i64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+/// A winning entry replaced at the draw because its buyer's account was missing
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct DrawFallback {
+    /// Entry the seed selected
+    pub skipped_index: u32,
+
+    /// Entry that won instead
+    pub selected_index: u32,
+}
 
 // ============================================================================
-// Account Structures
+// Instruction Parameters
 // ============================================================================
 
-#[derive(Accounts)]
-#[instruction(raffle_id: u64)]
-pub struct CreateRaffle<'info> {
-    #[account(
-        init,
-        payer = creator,
-        space = 8 + Raffle::INIT_SPACE,
-        seeds = [b"raffle", creator.key().as_ref(), raffle_id.to_le_bytes().as_ref()],
-        bump
-    )]
-    pub raffle: Account<'info, Raffle>,
+/// Optional features chosen when creating a raffle
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RaffleOptions {
+    /// How winning odds are weighted across entries
+    pub selection_mode: SelectionMode,
 
-    #[account(mut)]
-    pub creator: Signer<'info>,
+    /// Minimum tickets that must sell by `end_time` (0 = no minimum)
+    pub min_tickets: u32,
 
-    pub system_program: Program<'info, System>,
+    /// Optional charity receiving a share of the pot
+    pub charity: Option<Pubkey>,
+
+    /// Charity share of the pot in basis points
+    pub charity_bps: u16,
+
+    /// Mint whose holders pay a discounted ticket price
+    pub discount_mint: Option<Pubkey>,
+
+    /// Holder discount in basis points of `ticket_price`
+    pub discount_bps: u16,
+
+    /// Lamports paid from the pot to whoever triggers the draw
+    pub keeper_reward: u64,
+
+    /// Number of distinct winners to draw (1 to `MAX_WINNERS`)
+    pub winners: u8,
+
+    /// Revenue at which sales pause until the creator confirms (0 = disabled)
+    pub soft_cap: u64,
+
+    /// Where the draw seed comes from
+    pub randomness_source: RandomnessSource,
+
+    /// Hash of the creator's secret, required for `CommitReveal`
+    pub commitment: Option<[u8; 32]>,
+
+    /// Maximum lamports the raffle may collect (0 = no cap)
+    pub max_total_revenue: u64,
+
+    /// End the raffle once no full-price ticket fits under the cap
+    pub end_at_revenue_cap: bool,
+
+    /// Limit every wallet to a single ticket
+    pub one_per_wallet: bool,
+
+    /// Minimum ticket revenue that must be collected by `end_time` (0 = no minimum)
+    pub min_pot: u64,
+
+    /// Keep the creator fee in the raffle for a later `claim_fees`
+    pub escrow_fees: bool,
+
+    /// Slot at which entrants must have held the gating asset (0 = no snapshot)
+    pub snapshot_slot: u64,
+
+    /// Attestor co-signing purchases for snapshot holders, required with `snapshot_slot`
+    pub snapshot_attestor: Option<Pubkey>,
+
+    /// First displayed ticket number (e.g. 1 to number tickets from 1)
+    pub ticket_number_offset: u32,
+
+    /// Separate prizes drawn one by one with `draw_next_prize` (empty = pot raffle)
+    pub prizes: Vec<PrizeSpec>,
+
+    /// Let one buyer win several prizes
+    pub allow_multiple_wins: bool,
+
+    /// Lamports the creator posts as a bond for delivering off-chain prizes (0 = none)
+    pub bond: u64,
+
+    /// Client-chosen key making creation safe to retry (`raffle_id` derives from it)
+    pub idempotency_key: Option<[u8; 16]>,
+
+    /// Price at `end_time`, moving linearly from `ticket_price` (0 = fixed price)
+    pub end_price: u64,
+
+    /// Leave the most recent entry out of the draw (needs at least 2 entries)
+    pub exclude_last_buyer: bool,
+
+    /// Let the creator, calling as keeper, draw before `end_time`
+    pub allow_early_draw: bool,
+
+    /// Upper bound on the keeper tip, at least `keeper_reward` (0 = uncapped)
+    pub max_keeper_tip: u64,
+
+    /// Keep the prize in the raffle for the winner to `claim_prize`, single winner only
+    pub escrow_prize: bool,
+
+    /// Share of the pot split between non-winners by entries, in basis points
+    pub consolation_bps: u16,
+
+    /// Advertised collection of the prize NFT, recorded unverified
+    pub prize_collection: Option<Pubkey>,
+
+    /// Unix timestamp sales open at, before `end_time` (0 or past = open immediately)
+    pub sale_start: i64,
+
+    /// Lamports the creator deposits as a guaranteed prize, returned if the raffle fails
+    pub fixed_prize: u64,
+
+    /// Draw fewer winners when there are fewer distinct buyers, instead of failing
+    pub shrink_winners: bool,
+
+    /// Future beacon round to draw from, required for `Beacon` raffles
+    pub beacon_round: u64,
+
+    /// Key relaying the beacon value, required for `Beacon` raffles
+    pub beacon_signer: Option<Pubkey>,
+
+    /// Release an escrowed prize in this many tranches (0 = all at once)
+    pub vesting_tranches: u8,
+
+    /// Seconds between vesting tranches, required with `vesting_tranches`
+    pub vesting_interval: i64,
+
+    /// Parent raffle the single winner advances to (passed as `parent_raffle`)
+    pub parent: Option<Pubkey>,
+
+    /// Share of the pot funding the parent's pot, in basis points
+    pub parent_share_bps: u16,
+
+    /// Let anyone draw as soon as the raffle sells out, instead of at `end_time`
+    pub auto_draw_on_sellout: bool,
+
+    /// Pay the creator fee into the creator's treasury (passed as `treasury`)
+    pub use_treasury: bool,
+
+    /// Replace a winner whose account is missing at the draw with the next
+    /// entry whose account was passed, instead of failing the draw (requires
+    /// `draw_authority`, as the drawer chooses which accounts to pass)
+    pub allow_fallback: bool,
+
+    /// Seconds before `end_time` in which a purchase extends it by
+    /// `ANTI_SNIPE_EXTENSION` (0 = off)
+    pub anti_snipe_window: i64,
+
+    /// Seconds after creation during which `cancel_raffle` refunds sold
+    /// tickets (0 = only raffles without sales can be cancelled)
+    pub cooling_off: i64,
+
+    /// Mint winners must hold at draw time, required with `require_gate_at_draw`
+    pub gate_mint: Option<Pubkey>,
+
+    /// Skip winners who no longer hold `gate_mint` at the draw for the next
+    /// entry that does (`draw_winner` only; tickets cannot become NFTs)
+    pub require_gate_at_draw: bool,
+
+    /// Hash of a code the creator gives the winner off-chain, revealed with
+    /// `reveal_claim_code` before a physical prize is handed over
+    pub claim_code: Option<[u8; 32]>,
+
+    /// Minimum distinct buyers by `end_time`, failing the raffle otherwise
+    /// (0 = no minimum)
+    pub min_unique_buyers: u32,
+
+    /// Account that alone may draw, e.g. an operations wallet separate from
+    /// the creator (`None` = any keeper)
+    pub draw_authority: Option<Pubkey>,
 }
 
-#[derive(Accounts)]
-pub struct BuyTicket<'info> {
-    #[account(
-        mut,
-        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
-        bump = raffle.bump
-    )]
-    pub raffle: Account<'info, Raffle>,
+/// A separate prize awarded to one entry
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct PrizeSpec {
+    /// Short description shown to entrants (e.g. "Genesis NFT #12")
+    #[max_len(32)]
+    pub label: String,
+}
 
-    #[account(
-        init,
-        payer = buyer,
-        space = 8 + Ticket::INIT_SPACE,
-        seeds = [b"ticket", raffle.key().as_ref(), raffle.total_tickets_sold.to_le_bytes().as_ref()],
-        bump
-    )]
-    pub ticket: Account<'info, Ticket>,
+// ============================================================================
+// Return Data
+// ============================================================================
 
-    #[account(mut)]
-    pub buyer: Signer<'info>,
+/// Result of the `is_purchasable` view
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PurchaseStatus {
+    /// Whether `buy_ticket` would accept a purchase right now
+    pub purchasable: bool,
 
-    pub system_program: Program<'info, System>,
+    /// Why a purchase would be rejected (`None` when purchasable)
+    pub reason: PurchaseBlock,
 }
 
-#[derive(Accounts)]
-pub struct DrawWinner<'info> {
-    #[account(
-        mut,
-        seeds = [b"raffle", raffle.creator.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
-        bump = raffle.bump
-    )]
-    pub raffle: Account<'info, Raffle>,
+/// Result of the `creator_stats` view
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CreatorStats {
+    /// Number of raffles aggregated
+    pub total_raffles: u32,
+
+    /// Tickets sold across all raffles
+    pub total_tickets_sold: u64,
+
+    /// Ticket revenue across all raffles, in lamports
+    pub total_revenue: u64,
+
+    /// Creator fees from completed raffles, in lamports
+    pub total_fees_earned: u64,
+}
 
-    /// CHECK: Creator account to receive fee
-    #[account(mut)]
-    pub creator: UncheckedAccount<'info>,
+/// Result of the `leaderboard_stats` view
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LeaderboardStats {
+    /// Creator the totals belong to
+    pub creator: Pubkey,
 
-    pub system_program: Program<'info, System>,
-    // Winner account will be passed via remaining_accounts and found dynamically
+    /// Raffles completed with a winner since joining the leaderboard
+    pub total_raffles_completed: u32,
+
+    /// Ticket revenue of those raffles, in lamports
+    pub total_volume: u64,
+
+    /// Lamports paid or owed to their winners
+    pub total_prizes_paid: u64,
 }
 
-// ============================================================================
-// Account Data Structures
-// ============================================================================
+/// Result of the `simulate_for` view
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct DrawSimulation {
+    /// Whether the account would be drawn as a winner at the current slot
+    pub wins: bool,
 
-#[account]
-#[derive(InitSpace)]
-pub struct Raffle {
-    /// Creator of the raffle
-    pub creator: Pubkey,
+    /// The account's share of the prize in lamports (0 unless it wins)
+    pub prize: u64,
+}
 
-    /// Price per ticket in lamports
-    pub ticket_price: u64,
+/// Lamport amounts each party receives from a draw
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct DistributionPlan {
+    /// Total lamports being distributed
+    pub pot: u64,
 
-    /// Maximum number of tickets
-    pub max_tickets: u32,
+    /// Amount paid to the keeper triggering the draw
+    pub keeper_tip: u64,
 
-    /// Unix timestamp when raffle ends
-    pub end_time: i64,
+    /// Amount paid to the winner
+    pub winner_prize: u64,
 
-    /// Current number of tickets sold
-    pub total_tickets_sold: u32,
+    /// Each winner's part of `winner_prize`, in draw order
+    pub winner_shares: Vec<u64>,
 
-    /// List of all ticket buyers (stores buyer pubkey for each ticket)
-    #[max_len(20)]
-    pub ticket_buyers: Vec<Pubkey>,
+    /// Amount paid to the creator
+    pub creator_fee: u64,
 
-    /// Winner's public key (None until drawn)
-    pub winner: Option<Pubkey>,
+    /// Charity receiving `charity_amount`, if configured
+    pub charity: Option<Pubkey>,
 
-    /// Current state of the raffle
-    pub state: RaffleState,
+    /// Amount paid to the charity
+    pub charity_amount: u64,
 
-    /// PDA bump seed
-    pub bump: u8,
+    /// Amount set aside for non-winners to `claim_consolation`
+    pub consolation_amount: u64,
 
-    /// Unique raffle identifier
-    pub raffle_id: u64,
+    /// Amount sent to the parent raffle's pot
+    pub parent_share: u64,
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct Ticket {
-    /// Associated raffle public key
-    pub raffle: Pubkey,
+/// Result of the `entry_distribution` view
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct EntryDistribution {
+    /// Number of distinct buyers holding entries
+    pub unique_buyers: u32,
 
-    /// Buyer/owner of the ticket
+    /// Buyers holding the most entries, largest first
+    pub top_buyers: Vec<BuyerEntries>,
+
+    /// Gini coefficient of entries per buyer, in basis points
+    pub concentration_bps: u16,
+}
+
+/// One buyer's entry count in `EntryDistribution`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BuyerEntries {
+    /// Buyer holding the entries
     pub buyer: Pubkey,
 
-    /// Ticket number (0-indexed)
-    pub ticket_number: u32,
+    /// Number of entries the buyer holds
+    pub entries: u32,
+}
 
-    /// Unix timestamp when ticket was purchased
-    pub purchase_time: i64,
+/// Result of the `ticker` view
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Ticker {
+    /// Tickets still available before the raffle sells out
+    pub tickets_remaining: u32,
 
-    /// PDA bump seed
+    /// Seconds until `end_time` (0 once passed)
+    pub seconds_remaining: i64,
+
+    /// Lamports currently in the pot
+    pub pot: u64,
+
+    /// Current `RaffleState`, as its discriminant
+    pub state: u8,
+}
+
+/// Result of the `derive_addresses` view
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RaffleAddresses {
+    /// Raffle PDA for the creator and raffle id
+    pub raffle: Pubkey,
+
+    /// Canonical bump of the raffle PDA
     pub bump: u8,
 }
 
+/// Result of the `verify_ticket` view
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TicketVerification {
+    /// Whether the ticket sits at the PDA derived for its entry
+    pub address_matches: bool,
+
+    /// Whether the ticket records the raffle it was checked against
+    pub raffle_matches: bool,
+}
+
 // ============================================================================
 // Enums
 // ============================================================================
@@ -355,6 +6772,213 @@ pub enum RaffleState {
 
     /// Winner has been drawn and prize distributed
     Completed,
+
+    /// Raffle ended under-subscribed, buyers can claim refunds
+    Failed,
+
+    /// Revenue reached the soft cap, sales wait for creator confirmation
+    PausedAtSoftCap,
+
+    /// Raffle is scheduled, sales open at `sale_start` via `open_sales`
+    Pending,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PurchaseBlock {
+    /// Nothing prevents a purchase
+    None,
+
+    /// Raffle is not in the Active state
+    NotActive,
+
+    /// Sales are paused at the soft cap
+    Paused,
+
+    /// All tickets have been sold
+    SoldOut,
+
+    /// Raffle end time has passed
+    Expired,
+
+    /// Sales have not opened yet (`Pending` until `sale_start`)
+    NotOpen,
+
+    /// The platform admin froze the raffle (see `admin_freeze`)
+    AdminFrozen,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum SelectionMode {
+    /// Every ticket has equal odds
+    PerTicket,
+
+    /// Odds are proportional to lamports contributed by each entry
+    PerLamport,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum RandomnessSource {
+    /// Seed is the draw slot (cheapest, predictable by validators)
+    SlotBased,
+
+    /// Seed is the most recent hash from the SlotHashes sysvar
+    SlotHashes,
+
+    /// Seed mixes a creator secret, committed at creation, with the draw slot
+    CommitReveal,
+
+    /// Seed comes from a VRF oracle (not yet supported)
+    Vrf,
+
+    /// Seed comes from a public randomness beacon round committed at creation
+    Beacon,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum TerminationReason {
+    /// The creator cancelled the raffle
+    CreatorCancelled,
+
+    /// Fewer than `min_tickets` were sold by `end_time`
+    MinNotMet,
+
+    /// The platform admin rescued the raffle's funds
+    AdminRescued,
+
+    /// One buyer held every entry and was refunded the pot
+    NoContest,
+
+    /// Revenue stayed below `min_pot` by `end_time`
+    PotBelowMinimum,
+
+    /// Fewer than `min_unique_buyers` distinct buyers entered by `end_time`
+    TooFewBuyers,
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+#[event]
+pub struct TicketPurchased {
+    pub raffle: Pubkey,
+    pub buyer: Pubkey,
+    pub ticket_number: u32,
+    pub global_sequence: u64,
+    pub purchase_time: i64,
+    pub verified: bool,
+}
+
+#[event]
+pub struct TicketsPurchased {
+    pub raffle: Pubkey,
+    pub buyer: Pubkey,
+    pub requested: u32,
+    pub granted: u32,
+    pub total_paid: u64,
+}
+
+#[event]
+pub struct DrawAttempted {
+    pub raffle: Pubkey,
+    pub caller: Pubkey,
+    pub attempt: u32,
+    pub slot: u64,
+}
+
+#[event]
+pub struct PrizeDrawn {
+    pub raffle: Pubkey,
+    pub prize_index: u8,
+    pub winner: Pubkey,
+    pub entry_index: u32,
+}
+
+#[event]
+pub struct NoContest {
+    pub raffle: Pubkey,
+    pub buyer: Pubkey,
+    pub refund: u64,
+    pub reason: TerminationReason,
+}
+
+#[event]
+pub struct RaffleVerified {
+    pub raffle: Pubkey,
+    pub verified: bool,
+}
+
+#[event]
+pub struct RaffleFailed {
+    pub raffle: Pubkey,
+    pub raffle_id: u64,
+    pub tickets_sold: u32,
+    pub min_tickets: u32,
+    pub reason: TerminationReason,
+    pub fixed_prize_refunded: u64,
+}
+
+#[event]
+pub struct FeesClawedBack {
+    pub raffle: Pubkey,
+    pub admin: Pubkey,
+    pub creator_fee: u64,
+    pub refund_pool: u64,
+}
+
+#[event]
+pub struct ClawbackRefundClaimed {
+    pub raffle: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ClaimCodeRevealed {
+    pub raffle: Pubkey,
+    pub winner: Pubkey,
+}
+
+#[event]
+pub struct RaffleCancelled {
+    pub raffle: Pubkey,
+    pub raffle_id: u64,
+    pub tickets_refunded: u32,
+    pub reason: TerminationReason,
+}
+
+#[event]
+pub struct RaffleAdminFrozen {
+    pub raffle: Pubkey,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct RaffleAdminUnfrozen {
+    pub raffle: Pubkey,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct FeeRecipientChanged {
+    pub raffle: Pubkey,
+    pub old_recipient: Pubkey,
+    pub new_recipient: Pubkey,
+}
+
+#[event]
+pub struct EndTimeExtended {
+    pub raffle: Pubkey,
+    pub old_end_time: i64,
+    pub new_end_time: i64,
+    pub extensions: u8,
+}
+
+#[event]
+pub struct WinnerFallback {
+    pub raffle: Pubkey,
+    pub skipped_index: u32,
+    pub selected_index: u32,
 }
 
 // ============================================================================
@@ -395,4 +7019,334 @@ pub enum ErrorCode {
 
     #[msg("Invalid winning ticket provided")]
     InvalidWinningTicket,
+
+    #[msg("Invalid platform configuration")]
+    InvalidConfig,
+
+    #[msg("Creator must wait before creating another raffle")]
+    CreateCooldownActive,
+
+    #[msg("Creator has too many active raffles")]
+    TooManyActiveRaffles,
+
+    #[msg("Min tickets cannot exceed max tickets")]
+    InvalidMinTickets,
+
+    #[msg("Minimum tickets have not been sold")]
+    MinTicketsNotMet,
+
+    #[msg("Minimum tickets have been sold")]
+    MinTicketsMet,
+
+    #[msg("Raffle has not failed")]
+    RaffleNotFailed,
+
+    #[msg("Not all tickets have been refunded")]
+    RefundsOutstanding,
+
+    #[msg("Ticket does not belong to this raffle")]
+    InvalidTicket,
+
+    #[msg("Raffle end time has passed")]
+    RaffleExpired,
+
+    #[msg("Charity account is missing or invalid")]
+    InvalidCharity,
+
+    #[msg("Prize splits are invalid for this raffle")]
+    InvalidSplits,
+
+    #[msg("Max tickets exceeds the platform limit")]
+    MaxTicketsTooLarge,
+
+    #[msg("Invalid holder discount configuration")]
+    InvalidDiscount,
+
+    #[msg("Discount token account is invalid or empty")]
+    InvalidDiscountToken,
+
+    #[msg("Raffle cannot be drawn in the slot it ended")]
+    DrawSameSlot,
+
+    #[msg("Ticket has already been minted as an NFT")]
+    TicketAlreadyTokenized,
+
+    #[msg("Ticket NFT holder account is missing or invalid")]
+    InvalidTicketNft,
+
+    #[msg("Winner count must be between 1 and the maximum allowed")]
+    InvalidWinnerCount,
+
+    #[msg("Not enough unique participants for the number of winners")]
+    NotEnoughParticipants,
+
+    #[msg("Raffle sales are paused at the soft cap")]
+    RafflePaused,
+
+    #[msg("Raffle is not paused at the soft cap")]
+    RaffleNotPaused,
+
+    #[msg("Page size must be between 1 and the maximum allowed")]
+    InvalidPageSize,
+
+    #[msg("Quantity must be greater than zero")]
+    InvalidQuantity,
+
+    #[msg("Ticket account is missing or does not match the next ticket number")]
+    InvalidTicketAccount,
+
+    #[msg("Raffle account was passed more than once")]
+    DuplicateRaffle,
+
+    #[msg("Gift recipient is invalid")]
+    InvalidRecipient,
+
+    #[msg("Purchase nonce is stale")]
+    StaleNonce,
+
+    #[msg("Raffle has sold tickets")]
+    RaffleNotEmpty,
+
+    #[msg("Commit-reveal raffles require a commitment")]
+    MissingCommitment,
+
+    #[msg("Randomness source is not supported")]
+    UnsupportedRandomnessSource,
+
+    #[msg("Instruction does not apply to this randomness source")]
+    InvalidRandomnessSource,
+
+    #[msg("Randomness account is missing from remaining accounts")]
+    MissingRandomnessAccount,
+
+    #[msg("Creator secret has not been revealed")]
+    RandomnessNotRevealed,
+
+    #[msg("Creator secret was already revealed")]
+    SecretAlreadyRevealed,
+
+    #[msg("Secret does not match the commitment")]
+    InvalidReveal,
+
+    #[msg("Purchase would exceed the raffle's revenue cap")]
+    RevenueCapReached,
+
+    #[msg("Raffle has not been drawn")]
+    RaffleNotCompleted,
+
+    #[msg("Wallet has already entered this raffle")]
+    AlreadyEntered,
+
+    #[msg("Ticket revenue is below the raffle's minimum pot")]
+    PotBelowMinimum,
+
+    #[msg("Snapshot requires a past slot and an attestor")]
+    InvalidSnapshot,
+
+    #[msg("Purchase requires the snapshot attestor's signature")]
+    SnapshotAttestationRequired,
+
+    #[msg("Prizes must be labelled and fit the raffle")]
+    InvalidPrizes,
+
+    #[msg("Raffle has separate prizes, use draw_next_prize")]
+    PrizeRaffle,
+
+    #[msg("Raffle has no separate prizes")]
+    NotPrizeRaffle,
+
+    #[msg("Every prize has already been drawn")]
+    AllPrizesDrawn,
+
+    #[msg("Raffle duration is shorter than the platform minimum")]
+    DurationTooShort,
+
+    #[msg("Raffle has no outstanding bond")]
+    NoBond,
+
+    #[msg("Delivery window has closed")]
+    DeliveryWindowClosed,
+
+    #[msg("Delivery window is still open")]
+    DeliveryWindowOpen,
+
+    #[msg("Creator bond must be released or slashed first")]
+    BondOutstanding,
+
+    #[msg("Raffles differ in price, selection mode or entry rules")]
+    IncompatibleRaffles,
+
+    #[msg("Merged raffle would exceed its ticket or storage limit")]
+    MergeTooLarge,
+
+    #[msg("Raffle id does not match the idempotency key")]
+    InvalidIdempotencyKey,
+
+    #[msg("A raffle with this id already exists")]
+    RaffleAlreadyExists,
+
+    #[msg("A batched draw is in progress, continue it with draw_batch")]
+    BatchDrawInProgress,
+
+    #[msg("End price must differ from the ticket price")]
+    InvalidPriceCurve,
+
+    #[msg("Raffle state does not fit in the return data")]
+    StateTooLarge,
+
+    #[msg("Splits cannot be changed once tickets are sold")]
+    CannotModifyAfterSales,
+
+    #[msg("Raffle index does not record this state")]
+    InvalidRaffleIndex,
+
+    #[msg("Keeper reward exceeds the maximum keeper tip")]
+    InvalidKeeperTip,
+
+    #[msg("Claim exceeds the prize still owed")]
+    OverClaim,
+
+    #[msg("Escrowed prize has not been fully claimed")]
+    PrizeUnclaimed,
+
+    #[msg("Raffle entries are frozen for the draw")]
+    BuyersFrozen,
+
+    #[msg("Raffle entries were not frozen before the draw")]
+    BuyersNotFrozen,
+
+    #[msg("Capacity must grow and fit the maximum account size")]
+    InvalidCapacity,
+
+    #[msg("Raffle account is full, grow it to sell more tickets")]
+    RaffleAtCapacity,
+
+    #[msg("No consolation is owed to this buyer")]
+    NoConsolation,
+
+    #[msg("Consolation has already been claimed")]
+    ConsolationClaimed,
+
+    #[msg("Consolation shares have not all been claimed")]
+    ConsolationUnclaimed,
+
+    #[msg("Creator fee, charity, consolation and parent shares exceed 100% of the pot")]
+    SplitsExceedTotal,
+
+    #[msg("Lamport transfer did not conserve the total balance")]
+    LamportsNotConserved,
+
+    #[msg("Sale start must be before the end time")]
+    InvalidSaleStart,
+
+    #[msg("Raffle is not pending")]
+    RaffleNotPending,
+
+    #[msg("Sales have not opened yet")]
+    SalesNotOpen,
+
+    #[msg("Beacon raffles require a beacon round and signer")]
+    MissingBeacon,
+
+    #[msg("Beacon value has not been published")]
+    BeaconNotPublished,
+
+    #[msg("Beacon value was already published")]
+    BeaconAlreadyPublished,
+
+    #[msg("Beacon round does not match the committed round")]
+    BeaconRoundMismatch,
+
+    #[msg("Recorded entries do not match the tickets sold")]
+    EntryCountMismatch,
+
+    #[msg("Vesting needs an escrowed prize and a positive interval")]
+    InvalidVesting,
+
+    #[msg("Claim exceeds the vested part of the prize")]
+    PrizeNotVested,
+
+    #[msg("Raffle has already been completed")]
+    RaffleAlreadyCompleted,
+
+    #[msg("Parent raffle is invalid for this raffle")]
+    InvalidParent,
+
+    #[msg("Winner was already advanced to the parent raffle")]
+    WinnerAlreadyAdvanced,
+
+    #[msg("Creator balance cannot cover the bond and fixed prize")]
+    InsufficientDeposit,
+
+    #[msg("Sold-out raffle cannot be drawn before its end time")]
+    SelloutDrawNotAllowed,
+
+    #[msg("Raffle is frozen by the platform admin")]
+    RaffleFrozen,
+
+    #[msg("Creator treasury must be passed to use it")]
+    TreasuryRequired,
+
+    #[msg("Anti-snipe window must not be negative")]
+    InvalidAntiSnipeWindow,
+
+    #[msg("Cooling-off window must not be negative")]
+    InvalidCoolingOff,
+
+    #[msg("Cooling-off window has ended, raffles with sales cannot be cancelled")]
+    CoolingOffEnded,
+
+    #[msg("Draw-time gate needs a gate mint and is only checked by draw_winner")]
+    InvalidGate,
+
+    #[msg("Claim code has already been revealed")]
+    ClaimCodeAlreadyRevealed,
+
+    #[msg("Fewer distinct buyers than the raffle's minimum")]
+    TooFewBuyers,
+
+    #[msg("Raffle was created for a different draw algorithm version")]
+    AlgoVersionMismatch,
+
+    #[msg("Raffle is not frozen by the platform admin")]
+    RaffleNotFrozen,
+
+    #[msg("Clawback window has closed")]
+    ClawbackWindowClosed,
+
+    #[msg("No fees to claw back")]
+    NothingToClawBack,
+
+    #[msg("Clawback refund already claimed")]
+    ClawbackClaimed,
+
+    #[msg("Clawback refunds must be claimed before closing")]
+    ClawbackUnclaimed,
+
+    #[msg("Leaderboard entry does not belong to the raffle's creator")]
+    InvalidLeaderboard,
+
+    #[msg("Only the raffle's draw authority can draw it")]
+    NotDrawAuthority,
+
+    #[msg("Fallback draws require a draw authority")]
+    InvalidFallback,
+
+    #[msg("A drawn winner's gate token account was not passed")]
+    GateAccountMissing,
+
+    #[msg("The raffle creator's account is required to return the fixed prize")]
+    RaffleCreatorRequired,
+
+    #[msg("Fee recipient account does not match the raffle")]
+    InvalidFeeRecipient,
+
+    #[msg("Metadata URI is too long")]
+    InvalidMetadataUri,
+
+    #[msg("Buyer record does not match the raffle or buyer")]
+    InvalidBuyerRecord,
+
+    #[msg("Raffle still has open child raffles")]
+    ChildRafflesOpen,
 }